@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, fmt};
+
+use nom::IResult;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registers {
+    values: BTreeMap<char, i32>,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers {
+            values: BTreeMap::from([('X', 1)]),
+        }
+    }
+}
+
+impl Registers {
+    pub fn get(&self, register: char) -> i32 {
+        self.values.get(&register).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, register: char, value: i32) {
+        self.values.insert(register, value);
+    }
+}
+
+pub trait Instruction: fmt::Display {
+    fn cycles(&self) -> u32;
+
+    fn execute(&self, registers: &mut Registers);
+}
+
+pub type Program = Vec<Box<dyn Instruction>>;
+
+pub type Decoder = fn(&str) -> IResult<&str, Box<dyn Instruction>>;
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownInstruction {
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for UnknownInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: unknown instruction `{}`", self.line, self.text)
+    }
+}
+
+pub struct InstructionSet {
+    decoders: Vec<Decoder>,
+}
+
+impl InstructionSet {
+    pub fn new(decoders: &[Decoder]) -> InstructionSet {
+        InstructionSet {
+            decoders: decoders.to_vec(),
+        }
+    }
+
+    pub fn parse_program(&self, input: &str) -> Result<Program, UnknownInstruction> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                self.decoders
+                    .iter()
+                    .find_map(|decoder| match decoder(line.trim()) {
+                        Ok(("", instruction)) => Some(instruction),
+                        _ => None,
+                    })
+                    .ok_or_else(|| UnknownInstruction {
+                        line: i + 1,
+                        text: line.to_string(),
+                    })
+            })
+            .collect()
+    }
+}
+
+pub struct CycleState<'a> {
+    pub cycle: u32,
+    pub pc: usize,
+    pub instruction: &'a dyn Instruction,
+    pub registers: &'a Registers,
+}
+
+pub trait CycleObserver {
+    fn observe(&mut self, state: &CycleState);
+}
+
+pub struct Cpu<'a> {
+    program: &'a [Box<dyn Instruction>],
+    pub registers: Registers,
+    pub cycle: u32,
+    pub pc: usize,
+    pub current_instruction_remaining_cycles: u32,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Box<dyn Instruction>]) -> Cpu<'a> {
+        Cpu {
+            program,
+            registers: Registers::default(),
+            cycle: 1,
+            pc: 0,
+            current_instruction_remaining_cycles: 0,
+        }
+    }
+
+    pub fn halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    // The registers as seen during the current cycle, before the instruction completes.
+    pub fn state(&self) -> Option<CycleState<'_>> {
+        let instruction = self.program.get(self.pc)?;
+        Some(CycleState {
+            cycle: self.cycle,
+            pc: self.pc,
+            instruction: instruction.as_ref(),
+            registers: &self.registers,
+        })
+    }
+
+    pub fn step(&mut self) -> bool {
+        let Some(instruction) = self.program.get(self.pc) else {
+            return false;
+        };
+        if self.current_instruction_remaining_cycles == 0 {
+            self.current_instruction_remaining_cycles = instruction.cycles().max(1);
+        }
+
+        self.current_instruction_remaining_cycles -= 1;
+
+        if self.current_instruction_remaining_cycles == 0 {
+            instruction.execute(&mut self.registers);
+            self.pc += 1;
+        }
+        self.cycle += 1;
+        true
+    }
+
+    pub fn run(&mut self, observers: &mut [&mut dyn CycleObserver]) {
+        while let Some(state) = self.state() {
+            observers
+                .iter_mut()
+                .for_each(|observer| observer.observe(&state));
+            self.step();
+        }
+    }
+}
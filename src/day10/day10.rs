@@ -1,47 +1,100 @@
-use std::fs;
-
-use nom::{
-    branch::alt,
-    bytes::streaming::tag,
-    character::complete::{self, line_ending},
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
-};
-
-fn parse_noop(input: &str) -> IResult<&str, Instruction> {
+mod cpu;
+mod debugger;
+mod ocr;
+
+use std::{env, fmt, fs, io};
+
+use cpu::{Cpu, CycleObserver, CycleState, Instruction, InstructionSet, Program, Registers};
+use debugger::{Breakpoint, Debugger, Tracer, Watch};
+use nom::{bytes::complete::tag, character::complete, sequence::separated_pair, IResult};
+use ocr::read_screen;
+
+struct Noop;
+
+impl fmt::Display for Noop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "noop")
+    }
+}
+
+impl Instruction for Noop {
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, _registers: &mut Registers) {}
+}
+
+struct AddX(i32);
+
+impl fmt::Display for AddX {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "addx {}", self.0)
+    }
+}
+
+impl Instruction for AddX {
+    fn cycles(&self) -> u32 {
+        2
+    }
+
+    fn execute(&self, registers: &mut Registers) {
+        registers.set('X', registers.get('X') + self.0);
+    }
+}
+
+fn parse_noop(input: &str) -> IResult<&str, Box<dyn Instruction>> {
     let (input, _) = tag("noop")(input)?;
-    Ok((input, Instruction::Noop))
+    Ok((input, Box::new(Noop)))
 }
 
-fn parse_addx(input: &str) -> IResult<&str, Instruction> {
+fn parse_addx(input: &str) -> IResult<&str, Box<dyn Instruction>> {
     let (input, (_, value)) = separated_pair(tag("addx"), tag(" "), complete::i32)(input)?;
-    Ok((input, Instruction::AddX(value)))
+    Ok((input, Box::new(AddX(value))))
+}
+
+fn instruction_set() -> InstructionSet {
+    InstructionSet::new(&[parse_noop, parse_addx])
+}
+
+struct SignalStrength {
+    interesting_cycles: Vec<u32>,
+    sum: i32,
+}
+
+impl CycleObserver for SignalStrength {
+    fn observe(&mut self, state: &CycleState) {
+        if self.interesting_cycles.contains(&state.cycle) {
+            self.sum += state.cycle as i32 * state.registers.get('X');
+        }
+    }
 }
 
-fn parse_instructions(input: &str) -> IResult<&str, Vec<Instruction>> {
-    let (input, instructions) = separated_list1(line_ending, alt((parse_noop, parse_addx)))(input)?;
-    Ok((input, instructions))
+struct Crt {
+    screen: Vec<Vec<char>>,
 }
 
-enum Instruction {
-    Noop,
-    AddX(i32),
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            screen: vec![vec!['.'; 40]; 6],
+        }
+    }
 }
 
-impl Instruction {
-    fn get_num_cycles(&self) -> u32 {
-        match self {
-            Instruction::Noop => 1,
-            Instruction::AddX(_) => 2,
+impl CycleObserver for Crt {
+    fn observe(&mut self, state: &CycleState) {
+        let sprite = get_sprite(&state.registers.get('X'));
+        let coords = get_cycle_coords(&(state.cycle as i32));
+        if coords.1 < self.screen.len() as i32 && sprite.contains(&coords.0) {
+            self.screen[coords.1 as usize][coords.0 as usize] = '#';
         }
     }
 }
 
-fn print_screen(screen: &Vec<Vec<&str>>) {
+fn print_screen(screen: &[Vec<char>]) {
     screen.iter().for_each(|line| {
-        line.iter().for_each(|c| print!("{c}"));
-        println!("");
+        println!("{}", line.iter().collect::<String>());
     });
 }
 
@@ -55,50 +108,268 @@ fn get_cycle_coords(cycle: &i32) -> (i32, i32) {
     (x, y)
 }
 
+fn run(program: &Program) -> (SignalStrength, Crt) {
+    let mut signal_strength = SignalStrength {
+        interesting_cycles: vec![20, 60, 100, 140, 180, 220],
+        sum: 0,
+    };
+    let mut crt = Crt::default();
+    Cpu::new(program).run(&mut [&mut signal_strength, &mut crt]);
+    (signal_strength, crt)
+}
+
 fn main() {
     let input = fs::read_to_string("src/day10/input.txt").unwrap();
-    let (_, instructions) = parse_instructions(&input).unwrap();
+    let program = match instruction_set().parse_program(&input) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("Invalid program: {}", error);
+            return;
+        }
+    };
+
+    let (signal_strength, crt) = run(&program);
 
-    let interesting_cycles = [20, 60, 100, 140, 180, 220];
-    let mut sum_signal_strength = 0;
+    println!("Sum signal strength: {}", signal_strength.sum);
 
-    let mut screen = vec![vec!["."; 40]; 6];
+    print_screen(&crt.screen);
 
-    let mut cycle: i32 = 1;
-    let mut pc: usize = 0;
-    let mut x: i32 = 1;
-    let mut current_instruction_remaining_cycles = 0;
+    match read_screen(&crt.screen) {
+        Ok(text) => println!("Screen text: {}", text),
+        Err(error) => eprintln!("Could not read the screen: {}", error),
+    }
 
-    while pc < instructions.len() {
-        let instruction = &instructions[pc];
-        if current_instruction_remaining_cycles == 0 {
-            current_instruction_remaining_cycles = instruction.get_num_cycles();
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if let Some(path) = args.iter().find_map(|arg| arg.strip_prefix("--trace=")) {
+        let mut tracer = Tracer::default();
+        Cpu::new(&program).run(&mut [&mut tracer]);
+        fs::write(path, tracer.to_csv()).unwrap();
+    }
+    if args.iter().any(|arg| arg == "--debug") {
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.breakpoints = args
+            .iter()
+            .filter_map(|arg| arg.strip_prefix("--break="))
+            .filter_map(Breakpoint::parse)
+            .collect();
+        debugger.watches = args
+            .iter()
+            .filter_map(|arg| arg.strip_prefix("--watch="))
+            .filter_map(Watch::parse)
+            .collect();
+        debugger.run_interactive(io::stdin().lock());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use debugger::Stop;
+
+    use super::*;
+
+    struct XValues(Vec<(u32, i32)>);
+
+    impl CycleObserver for XValues {
+        fn observe(&mut self, state: &CycleState) {
+            self.0.push((state.cycle, state.registers.get('X')));
+        }
+    }
+
+    struct MulY(i32);
+
+    impl fmt::Display for MulY {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "muly {}", self.0)
         }
+    }
 
-        if interesting_cycles.contains(&cycle) {
-            sum_signal_strength += cycle * x;
+    impl Instruction for MulY {
+        fn cycles(&self) -> u32 {
+            3
         }
 
-        let sprite = get_sprite(&x);
-        let coords = get_cycle_coords(&cycle);
-        if sprite.contains(&coords.0) {
-            screen[coords.1 as usize][coords.0 as usize] = "#";
+        fn execute(&self, registers: &mut Registers) {
+            registers.set('Y', registers.get('X') * self.0);
         }
+    }
 
-        current_instruction_remaining_cycles -= 1;
+    fn parse_muly(input: &str) -> IResult<&str, Box<dyn Instruction>> {
+        let (input, (_, value)) = separated_pair(tag("muly"), tag(" "), complete::i32)(input)?;
+        Ok((input, Box::new(MulY(value))))
+    }
 
-        if current_instruction_remaining_cycles == 0 {
-            match instruction {
-                Instruction::Noop => {}
-                Instruction::AddX(value) => x += value,
-            }
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day10/test.txt").unwrap();
+        let program = instruction_set().parse_program(&input).unwrap();
+        let mut x_values = XValues(vec![]);
+        let mut cpu = Cpu::new(&program);
+        cpu.run(&mut [&mut x_values]);
+        assert_eq!(x_values.0, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.registers.get('X'), -1);
+        assert_eq!(cpu.cycle, 6);
+        assert!(cpu.halted() && !cpu.step());
 
-            pc += 1;
+        let program = instruction_set()
+            .parse_program(&"noop\n".repeat(220))
+            .unwrap();
+        assert_eq!(run(&program).0.sum, 720);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let program = instruction_set()
+            .parse_program(&"noop\n".repeat(240))
+            .unwrap();
+        let (_, crt) = run(&program);
+        for line in &crt.screen {
+            assert_eq!(
+                line.iter().collect::<String>(),
+                format!("###{}", ".".repeat(37))
+            );
         }
-        cycle += 1;
     }
 
-    println!("Sum signal strength: {sum_signal_strength}");
+    #[test]
+    fn test_instruction_set() {
+        let instructions = InstructionSet::new(&[parse_noop, parse_addx, parse_muly]);
+        let program = instructions
+            .parse_program("addx 4\nmuly 3\nnoop\n")
+            .unwrap();
+        let mut cpu = Cpu::new(&program);
+        let mut cycles = vec![];
+        while let Some(state) = cpu.state() {
+            cycles.push(format!("{} {} {}", state.cycle, cpu.pc, program[cpu.pc]));
+            cpu.step();
+        }
+        assert_eq!(cpu.registers.get('Y'), 15);
+        assert_eq!(cycles.len(), 6);
+        assert_eq!(cycles[3], "4 1 muly 3");
 
-    print_screen(&screen);
+        assert_eq!(
+            instruction_set().parse_program("noop\nmuly 3\n").err(),
+            Some(cpu::UnknownInstruction {
+                line: 2,
+                text: "muly 3".to_string()
+            })
+        );
+        assert!(instruction_set().parse_program("noopx\n").is_err());
+    }
+
+    fn render_text(text: &str) -> Vec<Vec<char>> {
+        (0..ocr::GLYPH_HEIGHT)
+            .map(|y| {
+                text.chars()
+                    .flat_map(|letter| {
+                        let (_, rows) = ocr::FONT.iter().find(|(c, _)| *c == letter).unwrap();
+                        format!("{}.", rows[y]).chars().collect::<Vec<char>>()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ocr() {
+        let letters = ocr::FONT
+            .iter()
+            .map(|(letter, _)| *letter)
+            .collect::<String>();
+        assert_eq!(read_screen(&render_text(&letters)), Ok(letters));
+        assert_eq!(
+            read_screen(&render_text("PZULBAUA")),
+            Ok("PZULBAUA".to_string())
+        );
+
+        let mut screen = render_text("HELLO");
+        screen[2][7] = '.';
+        screen[0][19] = '#';
+        let error = read_screen(&screen).unwrap_err();
+        assert_eq!(error.decoded, "H?L?O");
+        assert_eq!(error.positions, vec![1, 3]);
+        assert_eq!(
+            error.to_string(),
+            "unrecognized glyphs 1 (columns 5-8), 3 (columns 15-18) in `H?L?O`"
+        );
+
+        let program = instruction_set()
+            .parse_program(&"noop\n".repeat(240))
+            .unwrap();
+        let (_, crt) = run(&program);
+        let error = read_screen(&crt.screen).unwrap_err();
+        assert_eq!(error.decoded, "?       ");
+        assert_eq!(error.positions, vec![0]);
+    }
+
+    #[test]
+    fn test_debugger() {
+        let input = fs::read_to_string("src/day10/test.txt").unwrap();
+        let program = instruction_set().parse_program(&input).unwrap();
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.breakpoints = vec![Breakpoint::parse("pc:2").unwrap()];
+        assert_eq!(debugger.resume(), Stop::Breakpoint(Breakpoint::Pc(2)));
+        assert_eq!(debugger.describe(), "cycle 4 pc 2 `addx -5` X=4");
+        debugger.watches.push(Watch::parse("X < 0").unwrap());
+        assert_eq!(
+            debugger.resume(),
+            Stop::Watch {
+                watch: 0,
+                old: 4,
+                new: -1
+            }
+        );
+        assert_eq!(debugger.resume(), Stop::Halted);
+        assert_eq!(debugger.describe(), "halted after cycle 5 X=-1");
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.breakpoints = vec![Breakpoint::Cycle(3)];
+        assert_eq!(debugger.resume(), Stop::Breakpoint(Breakpoint::Cycle(3)));
+        assert_eq!(debugger.step(), None);
+        assert_eq!(debugger.cpu.cycle, 4);
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.breakpoints = vec![Breakpoint::Cycle(1), Breakpoint::Pc(0)];
+        assert_eq!(debugger.resume(), Stop::Breakpoint(Breakpoint::Cycle(1)));
+        assert_eq!(debugger.cpu.cycle, 1);
+        assert_eq!(debugger.resume(), Stop::Halted);
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.breakpoints = vec![Breakpoint::Pc(1)];
+        assert_eq!(debugger.step(), None);
+        assert_eq!(debugger.describe(), "cycle 2 pc 1 `addx 3` X=1");
+        assert_eq!(debugger.resume(), Stop::Halted);
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.run_interactive("w X\nc\nq\ns\n".as_bytes());
+        assert_eq!(debugger.cpu.cycle, 4);
+        assert_eq!(debugger.watches[0].to_string(), "X");
+
+        assert_eq!(Breakpoint::parse("line:3"), None);
+        assert_eq!(Watch::parse("X ~ 3"), None);
+        assert_eq!(Watch::parse("x >= -1").unwrap().to_string(), "X>=-1");
+        assert_eq!(Watch::parse("X!=0").unwrap().to_string(), "X!=0");
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.watches = vec![Watch::parse("X>=4").unwrap(), Watch::parse("X>4").unwrap()];
+        assert_eq!(
+            debugger.resume(),
+            Stop::Watch {
+                watch: 0,
+                old: 1,
+                new: 4
+            }
+        );
+
+        let mut tracer = Tracer::default();
+        Cpu::new(&program).run(&mut [&mut tracer]);
+        assert_eq!(
+            tracer.to_csv(),
+            "cycle,pc,instruction,x,pixel_x,pixel_y,lit\n\
+             1,0,noop,1,0,0,true\n\
+             2,1,addx 3,1,1,0,true\n\
+             3,1,addx 3,1,2,0,true\n\
+             4,2,addx -5,4,3,0,true\n\
+             5,2,addx -5,4,4,0,true\n"
+        );
+    }
 }
@@ -0,0 +1,338 @@
+use std::{fmt, io::BufRead};
+
+use super::{
+    cpu::{Cpu, CycleObserver, CycleState},
+    get_cycle_coords, get_sprite,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakpoint {
+    Cycle(u32),
+    Pc(usize),
+}
+
+impl Breakpoint {
+    pub fn parse(value: &str) -> Option<Breakpoint> {
+        let (kind, number) = value.trim().split_once(':')?;
+        match kind.trim() {
+            "cycle" => Some(Breakpoint::Cycle(number.trim().parse().ok()?)),
+            "pc" => Some(Breakpoint::Pc(number.trim().parse().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle:{}", cycle),
+            Breakpoint::Pc(pc) => write!(f, "pc:{}", pc),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    GreaterOrEqual,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+}
+
+impl Comparison {
+    // Two-character operators come first so `>=` is not read as `>`.
+    const ALL: [Comparison; 6] = [
+        Comparison::GreaterOrEqual,
+        Comparison::LessOrEqual,
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::Greater,
+        Comparison::Less,
+    ];
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::Less => "<",
+        }
+    }
+
+    fn holds(&self, value: i32, operand: i32) -> bool {
+        match self {
+            Comparison::GreaterOrEqual => value >= operand,
+            Comparison::LessOrEqual => value <= operand,
+            Comparison::Equal => value == operand,
+            Comparison::NotEqual => value != operand,
+            Comparison::Greater => value > operand,
+            Comparison::Less => value < operand,
+        }
+    }
+}
+
+// Without a condition a watch stops whenever the register changes, otherwise when the
+// condition starts to hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watch {
+    register: char,
+    condition: Option<(Comparison, i32)>,
+}
+
+impl Watch {
+    pub fn parse(value: &str) -> Option<Watch> {
+        let value = value.trim();
+        let register = value.chars().next().filter(|c| c.is_ascii_alphabetic())?;
+        let rest = value[1..].trim();
+        if rest.is_empty() {
+            return Some(Watch {
+                register: register.to_ascii_uppercase(),
+                condition: None,
+            });
+        }
+        let comparison = Comparison::ALL
+            .into_iter()
+            .find(|comparison| rest.starts_with(comparison.symbol()))?;
+        let operand = rest[comparison.symbol().len()..]
+            .trim()
+            .parse::<i32>()
+            .ok()?;
+        Some(Watch {
+            register: register.to_ascii_uppercase(),
+            condition: Some((comparison, operand)),
+        })
+    }
+
+    fn holds(&self, value: i32) -> bool {
+        match self.condition {
+            None => true,
+            Some((comparison, operand)) => comparison.holds(value, operand),
+        }
+    }
+
+    fn triggered(&self, old: i32, new: i32) -> bool {
+        match self.condition {
+            None => old != new,
+            Some(_) => !self.holds(old) && self.holds(new),
+        }
+    }
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.condition {
+            Some((comparison, operand)) => {
+                write!(f, "{}{}{}", self.register, comparison.symbol(), operand)
+            }
+            None => write!(f, "{}", self.register),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    Watch { watch: usize, old: i32, new: i32 },
+    Halted,
+}
+
+pub struct Debugger<'a> {
+    pub cpu: Cpu<'a>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watches: Vec<Watch>,
+    // Set once the current cycle has been shown, so continuing from a breakpoint moves on.
+    stopped: bool,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(cpu: Cpu<'a>) -> Debugger<'a> {
+        Debugger {
+            cpu,
+            breakpoints: vec![],
+            watches: vec![],
+            stopped: false,
+        }
+    }
+
+    // Program counter breakpoints only stop at the first cycle of an instruction.
+    fn breakpoint_hit(&self) -> Option<Breakpoint> {
+        let state = self.cpu.state()?;
+        let starting = self.cpu.current_instruction_remaining_cycles == 0;
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| match breakpoint {
+                Breakpoint::Cycle(cycle) => state.cycle == *cycle,
+                Breakpoint::Pc(pc) => starting && state.pc == *pc,
+            })
+            .copied()
+    }
+
+    pub fn step(&mut self) -> Option<Stop> {
+        let stop = self.advance();
+        self.stopped = true;
+        stop
+    }
+
+    fn advance(&mut self) -> Option<Stop> {
+        let before = self
+            .watches
+            .iter()
+            .map(|watch| self.cpu.registers.get(watch.register))
+            .collect::<Vec<i32>>();
+        if !self.cpu.step() {
+            return Some(Stop::Halted);
+        }
+        for (i, (watch, old)) in self.watches.iter().zip(before).enumerate() {
+            let new = self.cpu.registers.get(watch.register);
+            if watch.triggered(old, new) {
+                return Some(Stop::Watch { watch: i, old, new });
+            }
+        }
+        self.cpu.halted().then_some(Stop::Halted)
+    }
+
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            if !self.stopped {
+                if let Some(breakpoint) = self.breakpoint_hit() {
+                    self.stopped = true;
+                    return Stop::Breakpoint(breakpoint);
+                }
+            }
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+            self.stopped = false;
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.cpu.state() {
+            Some(state) => format!(
+                "cycle {} pc {} `{}` X={}",
+                state.cycle,
+                state.pc,
+                state.instruction,
+                state.registers.get('X')
+            ),
+            None => format!(
+                "halted after cycle {} X={}",
+                self.cpu.cycle - 1,
+                self.cpu.registers.get('X')
+            ),
+        }
+    }
+
+    fn print_stop(&self, stop: Option<Stop>) {
+        match stop {
+            Some(Stop::Breakpoint(breakpoint)) => println!("breakpoint {}", breakpoint),
+            Some(Stop::Watch { watch, old, new }) => {
+                println!("watch {}: {} -> {}", self.watches[watch], old, new)
+            }
+            Some(Stop::Halted) => println!("program halted"),
+            None => {}
+        }
+        println!("{}", self.describe());
+    }
+
+    pub fn run_interactive(&mut self, commands: impl BufRead) {
+        println!("[s]tep, [c]ontinue, [b]reak cycle:N|pc:N, [w]atch X|X>N, [l]ist, [q]uit");
+        println!("{}", self.describe());
+        for command in commands.lines() {
+            let command = command.unwrap();
+            let (name, argument) = command
+                .trim()
+                .split_once(' ')
+                .unwrap_or((command.trim(), ""));
+            match name {
+                "" | "s" => {
+                    let stop = self.step();
+                    self.print_stop(stop);
+                }
+                "c" => {
+                    let stop = self.resume();
+                    self.print_stop(Some(stop));
+                }
+                "b" => match Breakpoint::parse(argument) {
+                    Some(breakpoint) => self.breakpoints.push(breakpoint),
+                    None => println!("Invalid breakpoint: {}", argument),
+                },
+                "w" => match Watch::parse(argument) {
+                    Some(watch) => self.watches.push(watch),
+                    None => println!("Invalid watch: {}", argument),
+                },
+                "l" => {
+                    self.breakpoints
+                        .iter()
+                        .for_each(|breakpoint| println!("breakpoint {}", breakpoint));
+                    self.watches
+                        .iter()
+                        .for_each(|watch| println!("watch {}", watch));
+                }
+                "q" => break,
+                other => println!("Unknown command: {}", other),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TraceRow {
+    pub cycle: u32,
+    pub pc: usize,
+    pub instruction: String,
+    pub x: i32,
+    pub pixel: (i32, i32),
+    pub lit: bool,
+}
+
+#[derive(Default)]
+pub struct Tracer {
+    pub rows: Vec<TraceRow>,
+}
+
+impl CycleObserver for Tracer {
+    fn observe(&mut self, state: &CycleState) {
+        let x = state.registers.get('X');
+        let pixel = get_cycle_coords(&(state.cycle as i32));
+        self.rows.push(TraceRow {
+            cycle: state.cycle,
+            pc: state.pc,
+            instruction: state.instruction.to_string(),
+            x,
+            pixel,
+            lit: get_sprite(&x).contains(&pixel.0),
+        });
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Tracer {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("cycle,pc,instruction,x,pixel_x,pixel_y,lit\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                row.cycle,
+                row.pc,
+                csv_field(&row.instruction),
+                row.x,
+                row.pixel.0,
+                row.pixel.1,
+                row.lit
+            ));
+        }
+        csv
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+const GLYPH_SPACING: usize = 1;
+
+pub const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, PartialEq)]
+pub struct UnrecognizedGlyphs {
+    pub decoded: String,
+    pub positions: Vec<usize>,
+}
+
+impl fmt::Display for UnrecognizedGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = self
+            .positions
+            .iter()
+            .map(|position| {
+                let column = position * (GLYPH_WIDTH + GLYPH_SPACING);
+                format!(
+                    "{} (columns {}-{})",
+                    position,
+                    column,
+                    column + GLYPH_WIDTH - 1
+                )
+            })
+            .collect::<Vec<String>>();
+        write!(
+            f,
+            "unrecognized glyphs {} in `{}`",
+            columns.join(", "),
+            self.decoded
+        )
+    }
+}
+
+fn read_glyph(screen: &[Vec<char>], position: usize) -> Option<char> {
+    let column = position * (GLYPH_WIDTH + GLYPH_SPACING);
+    let lit = |x: usize, y: usize| screen[y].get(x) == Some(&'#');
+    let spacing_lit = (0..GLYPH_HEIGHT)
+        .any(|y| (column + GLYPH_WIDTH..column + GLYPH_WIDTH + GLYPH_SPACING).any(|x| lit(x, y)));
+    if spacing_lit {
+        return None;
+    }
+    if (0..GLYPH_HEIGHT).all(|y| (column..column + GLYPH_WIDTH).all(|x| !lit(x, y))) {
+        return Some(' ');
+    }
+    FONT.iter()
+        .find(|(_, rows)| {
+            rows.iter().enumerate().all(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .all(|(x, c)| (c == '#') == lit(column + x, y))
+            })
+        })
+        .map(|(letter, _)| *letter)
+}
+
+pub fn read_screen(screen: &[Vec<char>]) -> Result<String, UnrecognizedGlyphs> {
+    let width = screen.iter().map(|row| row.len()).max().unwrap_or(0);
+    let glyph_count = width.div_ceil(GLYPH_WIDTH + GLYPH_SPACING);
+    let mut decoded = String::new();
+    let mut positions = vec![];
+    for position in 0..glyph_count {
+        let glyph = match screen.len() {
+            GLYPH_HEIGHT => read_glyph(screen, position),
+            _ => None,
+        };
+        match glyph {
+            Some(letter) => decoded.push(letter),
+            None => {
+                decoded.push('?');
+                positions.push(position);
+            }
+        }
+    }
+    match positions.is_empty() {
+        true => Ok(decoded),
+        false => Err(UnrecognizedGlyphs { decoded, positions }),
+    }
+}
@@ -1,77 +1,324 @@
-use std::{collections::HashSet, fs, hash::Hash};
+use std::{env, fmt, fs};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn from_items(items: &[char]) -> Option<ItemSet> {
+        let mut set = ItemSet::default();
+        for item in items {
+            set.insert(*item)?;
+        }
+        Some(set)
+    }
+
+    fn insert(&mut self, item: char) -> Option<()> {
+        let priority = get_item_priority(&item)?;
+        self.0 |= 1 << priority;
+        Some(())
+    }
+
+    fn intersection(&self, other: &ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    fn union(&self, other: &ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    fn priorities(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (1..=52).filter(move |priority| bits & (1 << priority) != 0)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = char> {
+        self.priorities()
+            .map(|priority| get_item(priority).unwrap())
+    }
+}
+
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.iter().collect::<String>())
+    }
+}
 
 struct Rucksack {
-    compartment1: Vec<char>,
-    compartment2: Vec<char>,
+    items: Vec<char>,
+    compartment1: ItemSet,
+    compartment2: ItemSet,
 }
 
 impl Rucksack {
-    fn from_line(line: &str) -> Rucksack {
+    fn from_line(line: &str) -> Option<Rucksack> {
         let chars = line.chars().collect::<Vec<char>>();
         let size = chars.len();
-        Rucksack {
-            compartment1: chars.get(0..(size / 2)).unwrap().into(),
-            compartment2: chars.get((size / 2)..size).unwrap().into(),
-        }
+        Some(Rucksack {
+            compartment1: ItemSet::from_items(&chars[0..(size / 2)])?,
+            compartment2: ItemSet::from_items(&chars[(size / 2)..size])?,
+            items: chars,
+        })
+    }
+
+    fn compartments(&self) -> [&[char]; 2] {
+        let (compartment1, compartment2) = self.items.split_at(self.items.len() / 2);
+        [compartment1, compartment2]
+    }
+
+    fn describe_positions(&self, item: char) -> String {
+        self.compartments()
+            .iter()
+            .enumerate()
+            .map(
+                |(i, compartment)| match compartment.iter().position(|c| *c == item) {
+                    Some(position) => {
+                        format!("position {} in compartment {}", position + 1, i + 1)
+                    }
+                    None => format!("not in compartment {}", i + 1),
+                },
+            )
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn item_types_in_both_compartments(&self) -> ItemSet {
+        self.compartment1.intersection(&self.compartment2)
     }
 
-    fn item_types_in_both_compartments(&self) -> Vec<char> {
-        vector_intersection(&self.compartment1, &self.compartment2)
+    fn item_types(&self) -> ItemSet {
+        self.compartment1.union(&self.compartment2)
     }
 }
 
-fn get_item_priority(item: &char) -> i32 {
-    if item.is_uppercase() {
-        (*item as i32) - ('A' as i32) + 27
-    } else {
-        (*item as i32) - ('a' as i32) + 1
+fn get_item_priority(item: &char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some((*item as u32) - ('a' as u32) + 1),
+        'A'..='Z' => Some((*item as u32) - ('A' as u32) + 27),
+        _ => None,
     }
 }
 
-fn vector_intersection<T: Eq + Hash + Clone>(vec1: &Vec<T>, vec2: &Vec<T>) -> Vec<T> {
-    let c1: HashSet<T> = HashSet::from_iter(vec1.iter().cloned());
-    let c2: HashSet<T> = HashSet::from_iter(vec2.iter().cloned());
-    c1.intersection(&c2).cloned().collect()
+fn get_item(priority: u32) -> Option<char> {
+    match priority {
+        1..=26 => char::from_u32('a' as u32 + priority - 1),
+        27..=52 => char::from_u32('A' as u32 + priority - 27),
+        _ => None,
+    }
 }
 
-fn main() {
-    let rucksacks = fs::read_to_string("src/day3/input.txt")
-        .unwrap()
+#[derive(Debug, PartialEq)]
+enum BadgeError {
+    InvalidGroupSize,
+    IncompleteGroup { group: usize, size: usize },
+    NoBadge { group: usize },
+    AmbiguousBadge { group: usize, items: ItemSet },
+}
+
+impl fmt::Display for BadgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BadgeError::InvalidGroupSize => write!(f, "group size must be at least 1"),
+            BadgeError::IncompleteGroup { group, size } => {
+                write!(f, "group {group} only has {size} rucksacks")
+            }
+            BadgeError::NoBadge { group } => write!(f, "group {group} has no common item"),
+            BadgeError::AmbiguousBadge { group, items } => {
+                write!(f, "group {group} has several common items: {items}")
+            }
+        }
+    }
+}
+
+fn find_badge(group: &[Rucksack], group_number: usize) -> Result<char, BadgeError> {
+    let items = group
+        .iter()
+        .map(Rucksack::item_types)
+        .reduce(|acc, items| acc.intersection(&items))
+        .unwrap_or_default();
+    match items.len() {
+        0 => Err(BadgeError::NoBadge {
+            group: group_number,
+        }),
+        1 => Ok(items.iter().next().unwrap()),
+        _ => Err(BadgeError::AmbiguousBadge {
+            group: group_number,
+            items,
+        }),
+    }
+}
+
+fn find_badges(rucksacks: &[Rucksack], group_size: usize) -> Result<Vec<char>, BadgeError> {
+    if group_size == 0 {
+        return Err(BadgeError::InvalidGroupSize);
+    }
+    rucksacks
+        .chunks(group_size)
+        .enumerate()
+        .map(|(i, group)| {
+            if group.len() < group_size {
+                return Err(BadgeError::IncompleteGroup {
+                    group: i + 1,
+                    size: group.len(),
+                });
+            }
+            find_badge(group, i + 1)
+        })
+        .collect()
+}
+
+fn parse_rucksacks(input: &str) -> Vec<Rucksack> {
+    input
         .lines()
-        .map(Rucksack::from_line)
-        .collect::<Vec<Rucksack>>();
+        .map(|line| Rucksack::from_line(line).unwrap_or_else(|| panic!("Invalid rucksack: {line}")))
+        .collect()
+}
 
-    let sum_priorities = rucksacks
+fn part_1(rucksacks: &[Rucksack]) -> u32 {
+    rucksacks
         .iter()
         .map(|rucksack| {
             rucksack
                 .item_types_in_both_compartments()
-                .iter()
-                .map(get_item_priority)
-                .sum::<i32>()
-        })
-        .sum::<i32>();
-    println!("Sum priorities: {}", sum_priorities);
-
-    let sum_badge_priorities = rucksacks
-        .chunks(3)
-        .map(|group| {
-            group
-                .iter()
-                .map(|rucksack| {
-                    rucksack
-                        .compartment1
-                        .iter()
-                        .cloned()
-                        .chain(rucksack.compartment2.iter().cloned())
-                        .collect::<Vec<char>>()
-                })
-                .reduce(|acc, item| vector_intersection(&acc, &item))
-                .unwrap()
-                .iter()
-                .map(get_item_priority)
-                .sum::<i32>()
+                .priorities()
+                .sum::<u32>()
         })
-        .sum::<i32>();
-    println!("Sum badge priorities: {}", sum_badge_priorities);
+        .sum()
+}
+
+fn part_2(rucksacks: &[Rucksack], group_size: usize) -> Result<u32, BadgeError> {
+    let badges = find_badges(rucksacks, group_size)?;
+    Ok(badges
+        .iter()
+        .map(|badge| get_item_priority(badge).unwrap())
+        .sum())
+}
+
+fn print_report(rucksacks: &[Rucksack], group_size: usize) {
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        let duplicates = rucksack.item_types_in_both_compartments();
+        if duplicates.len() == 0 {
+            println!("Rucksack {}: no duplicated item", i + 1);
+        }
+        for item in duplicates.iter() {
+            println!(
+                "Rucksack {}: duplicated {} (priority {}) at {}",
+                i + 1,
+                item,
+                get_item_priority(&item).unwrap(),
+                rucksack.describe_positions(item)
+            );
+        }
+    }
+
+    if group_size == 0 {
+        println!("{}", BadgeError::InvalidGroupSize);
+        return;
+    }
+    for (i, group) in rucksacks.chunks(group_size).enumerate() {
+        let first_rucksack = i * group_size + 1;
+        if group.len() < group_size {
+            let error = BadgeError::IncompleteGroup {
+                group: i + 1,
+                size: group.len(),
+            };
+            println!("Group {}: {}", i + 1, error);
+            continue;
+        }
+        match find_badge(group, i + 1) {
+            Ok(badge) => {
+                println!(
+                    "Group {}: badge {} (priority {})",
+                    i + 1,
+                    badge,
+                    get_item_priority(&badge).unwrap()
+                );
+                for (j, rucksack) in group.iter().enumerate() {
+                    println!(
+                        "  Rucksack {}: {}",
+                        first_rucksack + j,
+                        rucksack.describe_positions(badge)
+                    );
+                }
+            }
+            Err(error) => println!("Group {}: {}", i + 1, error),
+        }
+    }
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let report = args.iter().any(|arg| arg == "--report");
+    let group_size = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(|arg| arg.parse::<usize>().unwrap())
+        .unwrap_or(3);
+
+    let input = fs::read_to_string("src/day3/input.txt").unwrap();
+    let rucksacks = parse_rucksacks(&input);
+
+    if report {
+        print_report(&rucksacks, group_size);
+    }
+
+    println!("Sum priorities: {}", part_1(&rucksacks));
+    match part_2(&rucksacks, group_size) {
+        Ok(sum) => println!("Sum badge priorities: {}", sum),
+        Err(error) => eprintln!("Invalid badge groups: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day3/test.txt").unwrap();
+        let rucksacks = parse_rucksacks(&input);
+        assert_eq!(part_1(&rucksacks), 157);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day3/test.txt").unwrap();
+        let rucksacks = parse_rucksacks(&input);
+        assert_eq!(part_2(&rucksacks, 3), Ok(70));
+    }
+
+    #[test]
+    fn test_badge_errors() {
+        let input = fs::read_to_string("src/day3/test.txt").unwrap();
+        let rucksacks = parse_rucksacks(&input);
+        assert_eq!(
+            part_2(&rucksacks, 2),
+            Err(BadgeError::AmbiguousBadge {
+                group: 1,
+                items: ItemSet::from_items(&['F', 'M', 'f', 'r', 's']).unwrap()
+            })
+        );
+        assert_eq!(
+            part_2(&rucksacks[..4], 3),
+            Err(BadgeError::IncompleteGroup { group: 2, size: 1 })
+        );
+        assert_eq!(part_2(&rucksacks, 6), Err(BadgeError::NoBadge { group: 1 }));
+    }
+
+    #[test]
+    fn test_item_set() {
+        let set = ItemSet::from_items(&['a', 'Z', 'a', 'q']).unwrap();
+        assert_eq!(set.priorities().collect::<Vec<u32>>(), vec![1, 17, 52]);
+        let other = ItemSet::from_items(&['b', 'Z']).unwrap();
+        assert_eq!(
+            set.intersection(&other).priorities().collect::<Vec<u32>>(),
+            vec![52]
+        );
+        assert_eq!(set.union(&other).priorities().count(), 4);
+        assert!(ItemSet::from_items(&['a', '1']).is_none());
+        assert_eq!(get_item_priority(&'!'), None);
+    }
 }
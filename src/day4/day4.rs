@@ -1,52 +1,173 @@
+mod interval_set;
+
 use std::{fs, ops::RangeInclusive};
 
+use interval_set::{coverage_profile, covered_at_least, IntervalSet};
+
 struct AssignmentPair {
-    first: RangeInclusive<i32>,
-    second: RangeInclusive<i32>,
+    assignments: Vec<IntervalSet<i32>>,
 }
 
 impl FromIterator<RangeInclusive<i32>> for AssignmentPair {
     fn from_iter<T: IntoIterator<Item = RangeInclusive<i32>>>(iter: T) -> Self {
-        let mut iter = iter.into_iter();
-        let first = iter.next().unwrap();
-        let second = iter.next().unwrap();
-        if iter.next().is_some() {
-            panic!("too many elements");
+        AssignmentPair {
+            assignments: iter.into_iter().map(IntervalSet::from).collect(),
         }
-        AssignmentPair { first, second }
     }
 }
 
 impl AssignmentPair {
     fn fully_contains(&self) -> bool {
-        (self.first.contains(self.second.start()) && self.first.contains(self.second.end()))
-            || (self.second.contains(self.first.start()) && self.second.contains(self.first.end()))
+        self.assignments.iter().enumerate().any(|(i, first)| {
+            self.assignments
+                .iter()
+                .enumerate()
+                .any(|(j, second)| i != j && first.is_superset(second))
+        })
     }
 
     fn overlaps(&self) -> bool {
-        self.first.contains(self.second.start())
-            || self.first.contains(self.second.end())
-            || self.second.contains(self.first.start())
-            || self.second.contains(self.first.end())
+        !self.covered_by_at_least(2).is_empty()
+    }
+
+    fn covered_by_at_least(&self, k: usize) -> IntervalSet<i32> {
+        covered_at_least(&self.assignments, k)
+    }
+
+    fn covered(&self) -> IntervalSet<i32> {
+        self.assignments
+            .iter()
+            .fold(IntervalSet::default(), |acc, assignment| {
+                acc.union(assignment)
+            })
+    }
+
+    fn covered_by_nobody(&self) -> IntervalSet<i32> {
+        self.covered().gaps()
+    }
+
+    fn covered_by_everyone(&self) -> IntervalSet<i32> {
+        self.assignments
+            .iter()
+            .cloned()
+            .reduce(|acc, assignment| acc.intersection(&assignment))
+            .unwrap_or_default()
     }
 }
 
-fn main() {
-    let assignment_pairs = fs::read_to_string("src/day4/input.txt")
-        .unwrap()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Elf {
+    line: usize,
+    index: usize,
+}
+
+struct CampAnalysis {
+    max_coverage: usize,
+    max_coverage_sections: IntervalSet<i32>,
+    covered_by_every_pair: IntervalSet<i32>,
+    span: Option<RangeInclusive<i32>>,
+    minimal_cover: Option<Vec<Elf>>,
+}
+
+fn analyse_camp(assignment_pairs: &[AssignmentPair]) -> CampAnalysis {
+    let elves = assignment_pairs
+        .iter()
+        .enumerate()
+        .flat_map(|(line, pair)| {
+            pair.assignments
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, assignment)| {
+                    assignment.span().map(|span| (Elf { line, index }, span))
+                })
+        })
+        .collect::<Vec<(Elf, RangeInclusive<i32>)>>();
+
+    let assignments = elves
+        .iter()
+        .map(|(_, span)| IntervalSet::from(span.clone()))
+        .collect::<Vec<IntervalSet<i32>>>();
+    let profile = coverage_profile(&assignments);
+    let max_coverage = profile.iter().map(|(_, depth)| *depth).max().unwrap_or(0);
+    let max_coverage_sections = profile
+        .into_iter()
+        .filter(|(_, depth)| *depth == max_coverage)
+        .map(|(range, _)| range)
+        .collect::<IntervalSet<i32>>();
+
+    let pair_coverage = assignment_pairs
+        .iter()
+        .map(AssignmentPair::covered)
+        .collect::<Vec<IntervalSet<i32>>>();
+    let covered_by_every_pair = covered_at_least(&pair_coverage, pair_coverage.len());
+
+    let span = assignments
+        .iter()
+        .fold(IntervalSet::default(), |acc, assignment| {
+            acc.union(assignment)
+        })
+        .span();
+    let minimal_cover = span.clone().and_then(|span| minimal_cover(elves, span));
+
+    CampAnalysis {
+        max_coverage,
+        max_coverage_sections,
+        covered_by_every_pair,
+        span,
+        minimal_cover,
+    }
+}
+
+fn minimal_cover(
+    mut elves: Vec<(Elf, RangeInclusive<i32>)>,
+    span: RangeInclusive<i32>,
+) -> Option<Vec<Elf>> {
+    elves.sort_by_key(|(_, assignment)| *assignment.start());
+
+    let mut cover = vec![];
+    let mut next = *span.start();
+    let mut i = 0;
+    loop {
+        let mut best: Option<(Elf, i32)> = None;
+        while i < elves.len() && *elves[i].1.start() <= next {
+            let (elf, assignment) = &elves[i];
+            if best.is_none_or(|(_, end)| *assignment.end() > end) {
+                best = Some((*elf, *assignment.end()));
+            }
+            i += 1;
+        }
+        let (elf, end) = best?;
+        if end < next {
+            return None;
+        }
+        cover.push(elf);
+        if end >= *span.end() {
+            return Some(cover);
+        }
+        next = end + 1;
+    }
+}
+
+fn parse_assignment_pairs(input: &str) -> Vec<AssignmentPair> {
+    input
         .lines()
         .map(|line| {
-            line.split(",")
+            line.split(',')
                 .map(|range| {
                     let numbers = range
-                        .split("-")
+                        .split('-')
                         .map(|i| i.parse::<i32>().unwrap())
                         .collect::<Vec<i32>>();
                     RangeInclusive::new(numbers[0], numbers[1])
                 })
                 .collect::<AssignmentPair>()
         })
-        .collect::<Vec<AssignmentPair>>();
+        .collect()
+}
+
+fn main() {
+    let input = fs::read_to_string("src/day4/input.txt").unwrap();
+    let assignment_pairs = parse_assignment_pairs(&input);
 
     let fully_containing_pairs = assignment_pairs
         .iter()
@@ -59,4 +180,126 @@ fn main() {
         .filter(|pair| pair.overlaps())
         .count();
     println!("Overlapping pairs: {}", overlapping_pairs);
+
+    let sections_covered_by_nobody = assignment_pairs
+        .iter()
+        .map(|pair| pair.covered_by_nobody().coverage())
+        .sum::<Option<i32>>()
+        .unwrap();
+    println!("Sections covered by nobody: {}", sections_covered_by_nobody);
+
+    let sections_covered_by_everyone = assignment_pairs
+        .iter()
+        .map(|pair| pair.covered_by_everyone().coverage())
+        .sum::<Option<i32>>()
+        .unwrap();
+    println!(
+        "Sections covered by everyone: {}",
+        sections_covered_by_everyone
+    );
+
+    let analysis = analyse_camp(&assignment_pairs);
+    println!(
+        "Maximum simultaneous coverage: {} elves at sections {:?}",
+        analysis.max_coverage,
+        analysis.max_coverage_sections.ranges()
+    );
+    println!(
+        "Sections covered by every pair: {:?}",
+        analysis.covered_by_every_pair.ranges()
+    );
+    match (&analysis.span, &analysis.minimal_cover) {
+        (Some(span), Some(cover)) => println!(
+            "Minimal cover of sections {:?}: {} elves ({})",
+            span,
+            cover.len(),
+            cover
+                .iter()
+                .map(|elf| format!("line {} elf {}", elf.line + 1, elf.index + 1))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        (Some(span), None) => println!("Sections {:?} cannot be covered", span),
+        (None, _) => println!("No assignments"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day4/test.txt").unwrap();
+        let assignment_pairs = parse_assignment_pairs(&input);
+        let result = assignment_pairs
+            .iter()
+            .filter(|pair| pair.fully_contains())
+            .count();
+        assert_eq!(result, 2);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day4/test.txt").unwrap();
+        let assignment_pairs = parse_assignment_pairs(&input);
+        let result = assignment_pairs
+            .iter()
+            .filter(|pair| pair.overlaps())
+            .count();
+        assert_eq!(result, 4);
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let a = IntervalSet::from_iter([1..=3, 7..=9, 4..=5]);
+        assert_eq!(a.ranges(), &[1..=5, 7..=9]);
+        assert_eq!(a.coverage(), Some(8));
+        assert_eq!(a.gaps().ranges(), &[6..=6]);
+
+        let b = IntervalSet::from_iter([3..=7u8]);
+        let a = IntervalSet::from_iter([1..=5u8, 7..=255]);
+        assert_eq!(a.intersection(&b).ranges(), &[3..=5, 7..=7]);
+        assert_eq!(a.difference(&b).ranges(), &[1..=2, 8..=255]);
+        assert_eq!(b.difference(&a).ranges(), &[6..=6]);
+        assert_eq!(a.union(&b).ranges(), &[1..=255]);
+        assert_eq!(a.union(&b).coverage(), Some(255));
+        assert_eq!(IntervalSet::from(0..=255u8).coverage(), None);
+        assert_eq!(IntervalSet::from(-128..=127i8).coverage(), None);
+        assert_eq!(IntervalSet::from(-64..=62i8).coverage(), Some(127));
+        assert!(a.union(&b).is_superset(&b));
+
+        let sets = [
+            IntervalSet::from(1..=6),
+            IntervalSet::from(4..=8),
+            IntervalSet::from(6..=10),
+        ];
+        assert_eq!(covered_at_least(&sets, 2).ranges(), &[4..=8]);
+        assert_eq!(covered_at_least(&sets, 3).ranges(), &[6..=6]);
+        assert_eq!(covered_at_least(&sets, 1).ranges(), &[1..=10]);
+    }
+
+    #[test]
+    fn test_camp_analysis() {
+        let input = fs::read_to_string("src/day4/test.txt").unwrap();
+        let assignment_pairs = parse_assignment_pairs(&input);
+        let analysis = analyse_camp(&assignment_pairs);
+        assert_eq!(analysis.max_coverage, 8);
+        assert_eq!(analysis.max_coverage_sections.ranges(), &[6..=6]);
+        assert_eq!(analysis.covered_by_every_pair.ranges(), &[]);
+        assert_eq!(analysis.span, Some(2..=9));
+        assert_eq!(
+            analysis.minimal_cover,
+            Some(vec![Elf { line: 3, index: 0 }, Elf { line: 2, index: 1 }])
+        );
+    }
+
+    #[test]
+    fn test_multiple_elves() {
+        let pairs = parse_assignment_pairs("1-2,5-6,4-8\n");
+        assert!(pairs[0].fully_contains());
+        assert_eq!(pairs[0].covered_by_nobody().ranges(), &[3..=3]);
+        assert_eq!(pairs[0].covered_by_at_least(2).ranges(), &[5..=6]);
+        assert!(pairs[0].covered_by_everyone().is_empty());
+    }
 }
@@ -0,0 +1,231 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, RangeInclusive, Sub},
+};
+
+pub trait Integer: Copy + Ord + Debug + Add<Output = Self> + Sub<Output = Self> {
+    fn one() -> Self;
+    fn zero() -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn one() -> Self {
+                    1
+                }
+
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalSet<T: Integer> {
+    intervals: Vec<RangeInclusive<T>>,
+}
+
+impl<T: Integer> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { intervals: vec![] }
+    }
+}
+
+impl<T: Integer> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        IntervalSet {
+            intervals: normalize(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl<T: Integer> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        IntervalSet::from_iter([range])
+    }
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn ranges(&self) -> &[RangeInclusive<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    // None when the number of covered values does not fit in `T`, e.g. all of `0..=255u8`.
+    pub fn coverage(&self) -> Option<T> {
+        self.intervals.iter().try_fold(T::zero(), |acc, range| {
+            let length = range
+                .end()
+                .checked_sub(*range.start())?
+                .checked_add(T::one())?;
+            acc.checked_add(length)
+        })
+    }
+
+    pub fn span(&self) -> Option<RangeInclusive<T>> {
+        let first = self.intervals.first()?;
+        let last = self.intervals.last()?;
+        Some(*first.start()..=*last.end())
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        self.intervals
+            .iter()
+            .chain(other.intervals.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            let start = *a.start().max(b.start());
+            let end = *a.end().min(b.end());
+            if start <= end {
+                intervals.push(start..=end);
+            }
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let mut j = 0;
+        for range in &self.intervals {
+            let mut start = Some(*range.start());
+            while j < other.intervals.len() && other.intervals[j].end() < range.start() {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(current) = start {
+                match other.intervals.get(k) {
+                    Some(removed) if removed.start() <= range.end() => {
+                        if *removed.start() > current {
+                            intervals.push(current..=(*removed.start() - T::one()));
+                        }
+                        start = removed.end().checked_add(T::one());
+                        k += 1;
+                    }
+                    _ => {
+                        intervals.push(current..=*range.end());
+                        start = None;
+                    }
+                }
+                if start.is_some_and(|start| start > *range.end()) {
+                    start = None;
+                }
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    pub fn is_superset(&self, other: &IntervalSet<T>) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn gaps(&self) -> IntervalSet<T> {
+        match self.span() {
+            Some(span) => IntervalSet::from(span).difference(self),
+            None => IntervalSet::default(),
+        }
+    }
+}
+
+pub fn coverage_profile<T: Integer>(sets: &[IntervalSet<T>]) -> Vec<(RangeInclusive<T>, usize)> {
+    let mut events = vec![];
+    for set in sets {
+        for range in set.ranges() {
+            events.push((Some(*range.start()), 1));
+            events.push((range.end().checked_add(T::one()), -1));
+        }
+    }
+    events.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    let max_end = sets
+        .iter()
+        .filter_map(|set| set.span())
+        .map(|span| *span.end())
+        .max();
+
+    let mut profile = vec![];
+    let mut depth: i64 = 0;
+    let mut i = 0;
+    while i < events.len() {
+        let position = events[i].0;
+        while i < events.len() && events[i].0 == position {
+            depth += events[i].1;
+            i += 1;
+        }
+        if let (Some(start), Some(max_end)) = (position, max_end) {
+            if depth > 0 {
+                let end = match events.get(i) {
+                    Some((Some(next), _)) => *next - T::one(),
+                    _ => max_end,
+                };
+                profile.push((start..=end, depth as usize));
+            }
+        }
+    }
+    profile
+}
+
+pub fn covered_at_least<T: Integer>(sets: &[IntervalSet<T>], k: usize) -> IntervalSet<T> {
+    coverage_profile(sets)
+        .into_iter()
+        .filter(|(_, depth)| *depth >= k.max(1))
+        .map(|(range, _)| range)
+        .collect()
+}
+
+fn normalize<T: Integer>(mut ranges: Vec<RangeInclusive<T>>) -> Vec<RangeInclusive<T>> {
+    ranges.retain(|range| range.start() <= range.end());
+    ranges.sort_by_key(|range| *range.start());
+    let mut intervals: Vec<RangeInclusive<T>> = vec![];
+    for range in ranges {
+        if let Some(last) = intervals.last_mut() {
+            let adjacent = match last.end().checked_add(T::one()) {
+                Some(next) => *range.start() <= next,
+                None => true,
+            };
+            if adjacent {
+                if range.end() > last.end() {
+                    *last = *last.start()..=*range.end();
+                }
+                continue;
+            }
+        }
+        intervals.push(range);
+    }
+    intervals
+}
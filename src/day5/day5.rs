@@ -1,19 +1,34 @@
-use std::fs;
+mod planner;
+
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead},
+};
 
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{alpha1, anychar, digit1, line_ending, not_line_ending},
-    multi::{count, many1, separated_list1},
+    multi::{many1, separated_list1},
     sequence::{delimited, terminated},
     IResult,
 };
+use planner::{plan, Target};
 
-#[derive(Debug)]
+const PLAN_STATE_LIMIT: usize = 100_000;
+
+#[derive(Clone, Debug, PartialEq)]
 struct Move {
     from: i32,
     to: i32,
     amount: i32,
+    line: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount, self.from, self.to)
+    }
 }
 
 fn parse_actual_crate(input: &str) -> IResult<&str, Option<char>> {
@@ -52,6 +67,7 @@ fn parse_move_line(input: &str) -> IResult<&str, Move> {
             from: from.parse::<i32>().unwrap(),
             to: to.parse::<i32>().unwrap(),
             amount: amount.parse::<i32>().unwrap(),
+            line: 0,
         },
     ))
 }
@@ -66,7 +82,7 @@ fn skip_line(input: &str) -> IResult<&str, Option<&str>> {
     Ok((input, None))
 }
 
-fn get_top_row(piles: &Vec<Vec<char>>) -> String {
+fn get_top_row(piles: &[Vec<char>]) -> String {
     piles
         .iter()
         .map(|pile| match pile.last() {
@@ -76,42 +92,513 @@ fn get_top_row(piles: &Vec<Vec<char>>) -> String {
         .collect::<String>()
 }
 
-fn main() {
-    let input = fs::read_to_string("src/day5/input.txt").unwrap();
-    let (input, crates) = parse_crate_rows(&input).unwrap();
-    let (input, _) = count(skip_line, 2)(input).unwrap();
-    let (_, moves) = parse_moves(input).unwrap();
+trait Crane {
+    fn name(&self) -> String;
+
+    fn arrange(&self, lifted: Vec<char>) -> Vec<char>;
+
+    fn apply(&self, piles: &mut [Vec<char>], m: &Move) {
+        let from = &mut piles[(m.from - 1) as usize];
+        let len = from.len();
+        let lifted = from
+            .drain(len.saturating_sub(m.amount as usize)..len)
+            .collect::<Vec<char>>();
+        let arranged = self.arrange(lifted);
+        piles[(m.to - 1) as usize].extend(arranged);
+    }
+}
+
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn arrange(&self, mut lifted: Vec<char>) -> Vec<char> {
+        lifted.reverse();
+        lifted
+    }
+}
+
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn arrange(&self, lifted: Vec<char>) -> Vec<char> {
+        lifted
+    }
+}
+
+struct LimitedLift {
+    capacity: usize,
+}
+
+impl Crane for LimitedLift {
+    fn name(&self) -> String {
+        format!("Limited lift ({} crates)", self.capacity)
+    }
+
+    fn arrange(&self, lifted: Vec<char>) -> Vec<char> {
+        lifted
+            .rchunks(self.capacity)
+            .flat_map(|chunk| chunk.iter().cloned())
+            .collect()
+    }
+}
+
+struct PairFlip;
+
+impl Crane for PairFlip {
+    fn name(&self) -> String {
+        "Pair flip".to_string()
+    }
+
+    fn arrange(&self, lifted: Vec<char>) -> Vec<char> {
+        lifted
+            .chunks(2)
+            .flat_map(|chunk| chunk.iter().rev().cloned())
+            .collect()
+    }
+}
+
+fn parse_crane(name: &str) -> Option<Box<dyn Crane>> {
+    match name {
+        "9000" => Some(Box::new(CrateMover9000)),
+        "9001" => Some(Box::new(CrateMover9001)),
+        "pairflip" => Some(Box::new(PairFlip)),
+        _ => {
+            let capacity = name.strip_prefix("limited:")?.parse::<usize>().ok()?;
+            if capacity == 0 {
+                return None;
+            }
+            Some(Box::new(LimitedLift { capacity }))
+        }
+    }
+}
+
+fn parse_piles(input: &str) -> IResult<&str, Vec<Vec<char>>> {
+    let (input, crates) = parse_crate_rows(input)?;
+    let (input, _) = skip_line(input)?;
 
     let mut piles: Vec<Vec<char>> = vec![];
     crates.iter().rev().for_each(|row| {
+        if piles.len() < row.len() {
+            piles.resize(row.len(), vec![]);
+        }
         row.iter().enumerate().for_each(|(i, crate_value)| {
             if let Some(crate_value) = crate_value {
-                if piles.len() < i + 1 {
-                    piles.resize(i + 1, vec![]);
-                }
                 piles[i].push(*crate_value);
             };
         })
     });
+    Ok((input, piles))
+}
+
+fn render_piles(piles: &[Vec<char>]) -> String {
+    let height = piles.iter().map(|pile| pile.len()).max().unwrap_or(0);
+    let mut lines = (0..height)
+        .rev()
+        .map(|level| {
+            piles
+                .iter()
+                .map(|pile| match pile.get(level) {
+                    Some(c) => format!("[{}]", c),
+                    None => "   ".to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>();
+    lines.push(
+        (1..=piles.len())
+            .map(|i| format!(" {:<2}", i))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn parse_input(input: &str) -> (Vec<Vec<char>>, Vec<Move>) {
+    let (rest, piles) = parse_piles(input).unwrap();
+    let (rest, _) = skip_line(rest).unwrap();
+    let (_, mut moves) = parse_moves(rest).unwrap();
+    let first_move_line = input.lines().count() - rest.lines().count() + 1;
+    for (i, m) in moves.iter_mut().enumerate() {
+        m.line = first_move_line + i;
+    }
+    (piles, moves)
+}
+
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    UnknownPile {
+        line: usize,
+        pile: i32,
+    },
+    NotEnoughCrates {
+        line: usize,
+        pile: i32,
+        amount: i32,
+        available: usize,
+    },
+}
 
-    let mut new_piles = piles.clone();
-    for move_instruction in &moves {
-        for _ in 0..move_instruction.amount {
-            let value = new_piles[(move_instruction.from - 1) as usize].pop();
-            if let Some(value) = value {
-                (new_piles[(move_instruction.to - 1) as usize]).push(value);
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::UnknownPile { line, pile } => {
+                write!(f, "line {line}: pile {pile} does not exist")
             }
+            MoveError::NotEnoughCrates {
+                line,
+                pile,
+                amount,
+                available,
+            } => write!(
+                f,
+                "line {line}: cannot move {amount} crates from pile {pile}, it only has {available}"
+            ),
+        }
+    }
+}
+
+struct Step {
+    m: Move,
+    lifted: Vec<char>,
+}
+
+struct StackYard<'a> {
+    crane: &'a dyn Crane,
+    piles: Vec<Vec<char>>,
+    done: Vec<Step>,
+    undone: Vec<Move>,
+}
+
+impl<'a> StackYard<'a> {
+    fn new(crane: &'a dyn Crane, piles: Vec<Vec<char>>) -> StackYard<'a> {
+        StackYard {
+            crane,
+            piles,
+            done: vec![],
+            undone: vec![],
         }
     }
-    println!("{}", get_top_row(&new_piles));
 
-    let mut new_piles = piles.clone();
-    for m in &moves {
-        let len = new_piles[(m.from - 1) as usize].len();
-        let mut to_move = new_piles[(m.from - 1) as usize]
-            .drain((len - m.amount as usize)..len)
-            .collect::<Vec<char>>();
-        new_piles[(m.to - 1) as usize].append(&mut to_move);
+    fn pile_index(&self, m: &Move, pile: i32) -> Result<usize, MoveError> {
+        if pile < 1 || pile as usize > self.piles.len() {
+            return Err(MoveError::UnknownPile { line: m.line, pile });
+        }
+        Ok((pile - 1) as usize)
+    }
+
+    fn execute(&mut self, m: &Move) -> Result<(), MoveError> {
+        let from = self.pile_index(m, m.from)?;
+        self.pile_index(m, m.to)?;
+        let available = self.piles[from].len();
+        if m.amount as usize > available {
+            return Err(MoveError::NotEnoughCrates {
+                line: m.line,
+                pile: m.from,
+                amount: m.amount,
+                available,
+            });
+        }
+
+        let lifted = self.piles[from][(available - m.amount as usize)..].to_vec();
+        self.crane.apply(&mut self.piles, m);
+        self.done.push(Step {
+            m: m.clone(),
+            lifted,
+        });
+        Ok(())
+    }
+
+    fn apply(&mut self, m: &Move) -> Result<(), MoveError> {
+        self.execute(m)?;
+        self.undone.clear();
+        Ok(())
+    }
+
+    fn run(&mut self, moves: &[Move]) -> Result<(), MoveError> {
+        moves.iter().try_for_each(|m| self.apply(m))
+    }
+
+    fn undo(&mut self) -> Option<Move> {
+        let step = self.done.pop()?;
+        let to = &mut self.piles[(step.m.to - 1) as usize];
+        to.truncate(to.len() - step.lifted.len());
+        self.piles[(step.m.from - 1) as usize].extend(step.lifted);
+        self.undone.push(step.m.clone());
+        Some(step.m)
+    }
+
+    fn redo(&mut self) -> Option<Result<Move, MoveError>> {
+        let m = self.undone.pop()?;
+        Some(self.execute(&m).map(|_| m))
+    }
+}
+
+fn print_piles(piles: &[Vec<char>]) {
+    print!("{}", render_piles(piles));
+}
+
+fn replay(yard: &mut StackYard, moves: &[Move]) -> Result<(), MoveError> {
+    print_piles(&yard.piles);
+    for m in moves {
+        yard.apply(m)?;
+        println!();
+        println!("line {}: {}", m.line, m);
+        print_piles(&yard.piles);
+    }
+    Ok(())
+}
+
+fn step_through(yard: &mut StackYard, moves: &[Move]) {
+    print_piles(&yard.piles);
+    println!("[n]ext, [u]ndo, [r]edo, [q]uit");
+    for command in io::stdin().lock().lines() {
+        let command = command.unwrap();
+        let result = match command.trim() {
+            "" | "n" => match yard.redo() {
+                Some(result) => Some(result),
+                None => moves
+                    .get(yard.done.len())
+                    .map(|m| yard.apply(m).map(|_| m.clone())),
+            },
+            "u" => yard.undo().map(Ok),
+            "r" => yard.redo(),
+            "q" => break,
+            other => {
+                println!("Unknown command: {}", other);
+                continue;
+            }
+        };
+        match result {
+            Some(Ok(m)) => {
+                let action = if command.trim() == "u" { "undo " } else { "" };
+                println!("{}line {}: {}", action, m.line, m);
+                print_piles(&yard.piles);
+            }
+            Some(Err(error)) => println!("{}", error),
+            None => println!("Nothing to do"),
+        }
+    }
+}
+
+fn main() {
+    let input = fs::read_to_string("src/day5/input.txt").unwrap();
+    let (piles, moves) = parse_input(&input);
+
+    let (flags, mut crane_names): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    if crane_names.is_empty() {
+        crane_names = vec!["9000".to_string(), "9001".to_string()];
+    }
+    let output = flags.iter().find_map(|flag| flag.strip_prefix("--output="));
+    let target = flags.iter().find_map(|flag| {
+        if let Some(top_row) = flag.strip_prefix("--plan=") {
+            Some(Target::TopRow(top_row.to_string()))
+        } else if let Some(path) = flag.strip_prefix("--plan-layout=") {
+            let diagram = fs::read_to_string(path).unwrap();
+            let (_, layout) = parse_piles(&diagram).unwrap();
+            Some(Target::Layout(layout))
+        } else {
+            None
+        }
+    });
+
+    for crane_name in &crane_names {
+        let crane = match parse_crane(crane_name) {
+            Some(crane) => crane,
+            None => {
+                eprintln!("Unknown crane: {}", crane_name);
+                continue;
+            }
+        };
+        if let Some(target) = &target {
+            match plan(crane.as_ref(), &piles, target, PLAN_STATE_LIMIT) {
+                Ok(moves) => {
+                    println!("{}: plan with {} moves", crane.name(), moves.len());
+                    moves.iter().for_each(|m| println!("{}", m));
+                }
+                Err(error) => println!("{}: {}", crane.name(), error),
+            }
+            continue;
+        }
+
+        let mut yard = StackYard::new(crane.as_ref(), piles.clone());
+        let result = if flags.iter().any(|flag| flag == "--step") {
+            step_through(&mut yard, &moves);
+            Ok(())
+        } else if flags.iter().any(|flag| flag == "--replay") {
+            replay(&mut yard, &moves)
+        } else {
+            yard.run(&moves)
+        };
+        match result {
+            Ok(()) => println!("{}: {}", crane.name(), get_top_row(&yard.piles)),
+            Err(error) => eprintln!("{}: {}", crane.name(), error),
+        }
+        if let Some(output) = output {
+            let path = if crane_names.len() > 1 {
+                format!("{}.{}", output, crane_name)
+            } else {
+                output.to_string()
+            };
+            fs::write(&path, render_piles(&yard.piles)).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use planner::PlanError;
+
+    use super::*;
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let (piles, moves) = parse_input(&input);
+        let mut yard = StackYard::new(&CrateMover9000, piles);
+        yard.run(&moves).unwrap();
+        assert_eq!(get_top_row(&yard.piles), "CMZ");
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let (piles, moves) = parse_input(&input);
+        let mut yard = StackYard::new(&CrateMover9001, piles);
+        yard.run(&moves).unwrap();
+        assert_eq!(get_top_row(&yard.piles), "MCD");
+    }
+
+    #[test]
+    fn test_crane_models() {
+        let lifted = vec!['a', 'b', 'c', 'd', 'e'];
+        assert_eq!(
+            LimitedLift { capacity: 1 }.arrange(lifted.clone()),
+            CrateMover9000.arrange(lifted.clone())
+        );
+        assert_eq!(
+            LimitedLift { capacity: 2 }.arrange(lifted.clone()),
+            vec!['d', 'e', 'b', 'c', 'a']
+        );
+        assert_eq!(PairFlip.arrange(lifted), vec!['b', 'a', 'd', 'c', 'e']);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let (piles, moves) = parse_input(&input);
+        let mut yard = StackYard::new(&CrateMover9001, piles.clone());
+        yard.run(&moves).unwrap();
+        let final_piles = yard.piles.clone();
+        while yard.undo().is_some() {}
+        assert_eq!(yard.piles, piles);
+        while yard.redo().is_some() {}
+        assert_eq!(yard.piles, final_piles);
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let (piles, moves) = parse_input(&input);
+        assert_eq!(moves[0].line, 6);
+        let mut yard = StackYard::new(&CrateMover9000, piles);
+        let m = Move {
+            from: 4,
+            to: 1,
+            amount: 1,
+            line: 7,
+        };
+        assert_eq!(
+            yard.apply(&m),
+            Err(MoveError::UnknownPile { line: 7, pile: 4 })
+        );
+        let m = Move {
+            from: 3,
+            to: 1,
+            amount: 2,
+            line: 8,
+        };
+        assert_eq!(
+            yard.apply(&m),
+            Err(MoveError::NotEnoughCrates {
+                line: 8,
+                pile: 3,
+                amount: 2,
+                available: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let diagram = input.split("\n\n").next().unwrap().to_string() + "\n";
+        let (_, piles) = parse_piles(&diagram).unwrap();
+        assert_eq!(render_piles(&piles), diagram);
+        let (_, reparsed) = parse_piles(&render_piles(&piles)).unwrap();
+        assert_eq!(reparsed, piles);
+    }
+
+    #[test]
+    fn test_render_wide_round_trip() {
+        let piles = (0..12)
+            .map(|i| ('A'..='Z').skip(i).take(i % 4).collect::<Vec<char>>())
+            .collect::<Vec<Vec<char>>>();
+        let diagram = render_piles(&piles);
+        assert!(diagram.ends_with(" 9   10  11  12\n"));
+        let (_, reparsed) = parse_piles(&diagram).unwrap();
+        assert_eq!(reparsed, piles);
+        assert_eq!(render_piles(&reparsed), diagram);
+    }
+
+    #[test]
+    fn test_plan() {
+        let input = fs::read_to_string("src/day5/test.txt").unwrap();
+        let (piles, moves) = parse_input(&input);
+        let mut yard = StackYard::new(&CrateMover9000, piles.clone());
+        yard.run(&moves).unwrap();
+        let target = Target::Layout(yard.piles.clone());
+
+        let planned = plan(&CrateMover9000, &piles, &target, 10_000).unwrap();
+        assert!(planned.len() <= moves.len());
+        let mut planned_yard = StackYard::new(&CrateMover9000, piles.clone());
+        planned_yard.run(&planned).unwrap();
+        assert_eq!(planned_yard.piles, yard.piles);
+
+        let target = Target::TopRow("MCD".to_string());
+        let planned = plan(&CrateMover9001, &piles, &target, 10_000).unwrap();
+        let mut planned_yard = StackYard::new(&CrateMover9001, piles.clone());
+        planned_yard.run(&planned).unwrap();
+        assert_eq!(get_top_row(&planned_yard.piles), "MCD");
+
+        let target = Target::Layout(vec![vec!['X'], vec![], vec![]]);
+        assert_eq!(
+            plan(&CrateMover9001, &piles, &target, 10_000),
+            Err(PlanError::Unreachable)
+        );
+        let target = Target::TopRow("BB".to_string());
+        assert_eq!(
+            plan(&CrateMover9001, &[vec!['A'], vec!['A']], &target, 10_000),
+            Err(PlanError::Unreachable)
+        );
+        let target = Target::TopRow("ZZZ".to_string());
+        assert_eq!(
+            plan(&CrateMover9001, &piles, &target, 10_000),
+            Err(PlanError::Unreachable)
+        );
+        let target = Target::TopRow("MCD".to_string());
+        assert_eq!(
+            plan(&CrateMover9000, &piles, &target, 10),
+            Err(PlanError::StateLimit { limit: 10 })
+        );
     }
-    println!("{}", get_top_row(&new_piles));
 }
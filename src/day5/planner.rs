@@ -0,0 +1,120 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+use super::{get_top_row, Crane, Move};
+
+type Piles = Vec<Vec<char>>;
+
+pub enum Target {
+    TopRow(String),
+    Layout(Piles),
+}
+
+impl Target {
+    fn is_reached(&self, piles: &[Vec<char>]) -> bool {
+        match self {
+            Target::TopRow(top_row) => get_top_row(piles) == *top_row,
+            Target::Layout(layout) => piles == layout.as_slice(),
+        }
+    }
+
+    fn is_reachable_from(&self, piles: &[Vec<char>]) -> bool {
+        let count_crates = |crates: &mut dyn Iterator<Item = char>| {
+            let mut counts = HashMap::new();
+            crates.for_each(|c| *counts.entry(c).or_insert(0) += 1);
+            counts
+        };
+        let available = count_crates(&mut piles.iter().flatten().copied());
+        match self {
+            Target::TopRow(top_row) => {
+                let needed = count_crates(&mut top_row.chars().filter(|c| *c != ' '));
+                top_row.chars().count() == piles.len()
+                    && needed
+                        .iter()
+                        .all(|(c, count)| available.get(c).is_some_and(|n| n >= count))
+            }
+            Target::Layout(layout) => {
+                layout.len() == piles.len()
+                    && count_crates(&mut layout.iter().flatten().copied()) == available
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    Unreachable,
+    StateLimit { limit: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::Unreachable => write!(f, "the target cannot be reached"),
+            PlanError::StateLimit { limit } => write!(f, "no plan found within {limit} states"),
+        }
+    }
+}
+
+fn possible_moves(piles: &[Vec<char>]) -> impl Iterator<Item = Move> + '_ {
+    (0..piles.len()).flat_map(move |from| {
+        (0..piles.len())
+            .filter(move |to| *to != from)
+            .flat_map(move |to| {
+                (1..=piles[from].len()).map(move |amount| Move {
+                    from: from as i32 + 1,
+                    to: to as i32 + 1,
+                    amount: amount as i32,
+                    line: 0,
+                })
+            })
+    })
+}
+
+pub fn plan(
+    crane: &dyn Crane,
+    piles: &[Vec<char>],
+    target: &Target,
+    max_states: usize,
+) -> Result<Vec<Move>, PlanError> {
+    if !target.is_reachable_from(piles) {
+        return Err(PlanError::Unreachable);
+    }
+
+    let mut parents: HashMap<Piles, Option<(Piles, Move)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    parents.insert(piles.to_vec(), None);
+    queue.push_back(piles.to_vec());
+
+    while let Some(state) = queue.pop_front() {
+        if target.is_reached(&state) {
+            let mut moves = vec![];
+            let mut current = state;
+            while let Some(Some((parent, m))) = parents.get(&current) {
+                moves.push(m.clone());
+                current = parent.clone();
+            }
+            moves.reverse();
+            for (i, m) in moves.iter_mut().enumerate() {
+                m.line = i + 1;
+            }
+            return Ok(moves);
+        }
+
+        for m in possible_moves(&state) {
+            let mut next = state.clone();
+            crane.apply(&mut next, &m);
+            if parents.contains_key(&next) {
+                continue;
+            }
+            if parents.len() >= max_states {
+                return Err(PlanError::StateLimit { limit: max_states });
+            }
+            parents.insert(next.clone(), Some((state.clone(), m)));
+            queue.push_back(next);
+        }
+    }
+    Err(PlanError::Unreachable)
+}
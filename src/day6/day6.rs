@@ -1,28 +1,305 @@
-use std::fs;
+use std::{
+    env,
+    fs::File,
+    io::{self, BufReader, Bytes, Read},
+    ops::Range,
+};
 
-fn find_different_characters_of_size(chars: &Vec<char>, size: usize) -> Option<i32> {
-    for (i, window) in chars.windows(size).enumerate() {
-        if window
-            .iter()
-            .all(|c| window.iter().filter(|ch| **ch == *c).count() == 1)
-        {
-            return Some((i + size) as i32);
+struct MarkerDetector {
+    size: usize,
+    last_seen: [usize; 256],
+    window_start: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(size: usize) -> MarkerDetector {
+        MarkerDetector {
+            size,
+            last_seen: [0; 256],
+            window_start: 0,
+            position: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> bool {
+        let seen = self.last_seen[byte as usize];
+        if seen > self.window_start {
+            self.window_start = seen;
+        }
+        self.position += 1;
+        self.last_seen[byte as usize] = self.position;
+        self.position - self.window_start >= self.size
+    }
+
+    fn reset(&mut self) {
+        self.window_start = self.position;
+    }
+}
+
+struct Markers<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            match byte {
+                Ok(byte) => {
+                    if self.detector.push(byte) {
+                        return Some(Ok(self.detector.position));
+                    }
+                }
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        None
+    }
+}
+
+fn markers<R: Read>(reader: R, size: usize) -> Markers<R> {
+    Markers {
+        bytes: BufReader::new(reader).bytes(),
+        detector: MarkerDetector::new(size),
+    }
+}
+
+fn find_different_characters_of_size<R: Read>(reader: R, size: usize) -> io::Result<Option<usize>> {
+    markers(reader, size).next().transpose()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FrameKind {
+    Preamble,
+    Packet,
+    Message,
+}
+
+#[derive(Debug, PartialEq)]
+struct Frame {
+    kind: FrameKind,
+    marker: Range<usize>,
+    payload: Range<usize>,
+}
+
+struct Protocol {
+    packet_marker_size: usize,
+    message_marker_size: usize,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol {
+            packet_marker_size: 4,
+            message_marker_size: 14,
         }
     }
-    return None;
+}
+
+impl Protocol {
+    // Each packet marker is followed by a message marker searched only after it, and the
+    // message payload runs to the next packet marker. Bytes before the first packet marker
+    // form a preamble, so the frames tile the whole stream.
+    fn decode<R: Read>(&self, reader: R) -> io::Result<Vec<Frame>> {
+        let mut packet_detector = MarkerDetector::new(self.packet_marker_size);
+        let mut message_detector = MarkerDetector::new(self.message_marker_size);
+        let mut frames = vec![Frame {
+            kind: FrameKind::Preamble,
+            marker: 0..0,
+            payload: 0..0,
+        }];
+        let mut length = 0;
+
+        for byte in BufReader::new(reader).bytes() {
+            let byte = byte?;
+            length += 1;
+            let (kind, detector) = match frames.last().map(|frame| frame.kind) {
+                Some(FrameKind::Packet) => (FrameKind::Message, &mut message_detector),
+                _ => (FrameKind::Packet, &mut packet_detector),
+            };
+            if detector.push(byte) {
+                let marker = (length - detector.size)..length;
+                if let Some(frame) = frames.last_mut() {
+                    frame.payload.end = marker.start;
+                }
+                frames.push(Frame {
+                    kind,
+                    payload: marker.end..marker.end,
+                    marker,
+                });
+                detector.reset();
+            }
+        }
+
+        if let Some(frame) = frames.last_mut() {
+            frame.payload.end = length;
+        }
+        frames.retain(|frame| frame.kind != FrameKind::Preamble || !frame.payload.is_empty());
+        Ok(frames)
+    }
 }
 
 fn main() {
-    let chars = fs::read_to_string("src/day6/input.txt")
-        .unwrap()
-        .chars()
-        .collect::<Vec<char>>();
+    let open_input = || File::open("src/day6/input.txt").unwrap();
+
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let parse_size = |prefix: &str| {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix(prefix))
+            .map(|size| size.parse::<usize>().unwrap())
+    };
+    let default_protocol = Protocol::default();
+    let protocol = Protocol {
+        packet_marker_size: parse_size("--packet-marker=")
+            .unwrap_or(default_protocol.packet_marker_size),
+        message_marker_size: parse_size("--message-marker=")
+            .unwrap_or(default_protocol.message_marker_size),
+    };
 
-    if let Some(first_start_of_packer_number) = find_different_characters_of_size(&chars, 4) {
+    if let Some(first_start_of_packer_number) =
+        find_different_characters_of_size(open_input(), 4).unwrap()
+    {
         println!("Start of packet number: {}", first_start_of_packer_number);
     };
 
-    if let Some(first_start_of_message_number) = find_different_characters_of_size(&chars, 14) {
+    if let Some(first_start_of_message_number) =
+        find_different_characters_of_size(open_input(), 14).unwrap()
+    {
         println!("Start of message number: {}", first_start_of_message_number);
     };
+
+    let start_of_message_count = markers(open_input(), 14)
+        .collect::<io::Result<Vec<usize>>>()
+        .unwrap()
+        .len();
+    println!("Start of message positions: {}", start_of_message_count);
+
+    let frames = protocol.decode(open_input()).unwrap();
+    if args.iter().any(|arg| arg == "--frames") {
+        frames.iter().for_each(|frame| {
+            println!(
+                "{:?} marker {:?} payload {:?} ({} bytes)",
+                frame.kind,
+                frame.marker,
+                frame.payload,
+                frame.payload.len()
+            )
+        });
+    }
+    let count_frames = |kind: FrameKind| frames.iter().filter(|frame| frame.kind == kind).count();
+    println!(
+        "Packets: {}, messages: {}",
+        count_frames(FrameKind::Packet),
+        count_frames(FrameKind::Message)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn test_part_1() {
+        for (signal, start_of_packet, _) in EXAMPLES {
+            let result = find_different_characters_of_size(signal.as_bytes(), 4).unwrap();
+            assert_eq!(result, Some(start_of_packet));
+        }
+    }
+
+    #[test]
+    fn test_part_2() {
+        for (signal, _, start_of_message) in EXAMPLES {
+            let result = find_different_characters_of_size(signal.as_bytes(), 14).unwrap();
+            assert_eq!(result, Some(start_of_message));
+        }
+    }
+
+    #[test]
+    fn test_all_markers() {
+        let result = markers("abcabbcd".as_bytes(), 3)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(result, vec![3, 4, 5, 8]);
+        let result = markers("aaaa".as_bytes(), 2).next().transpose().unwrap();
+        assert_eq!(result, None);
+    }
+
+    fn assert_tiles(frames: &[Frame], length: usize) {
+        let mut end = 0;
+        for frame in frames {
+            assert_eq!(frame.marker.start, end);
+            assert_eq!(frame.marker.end, frame.payload.start);
+            end = frame.payload.end;
+        }
+        assert_eq!(end, length);
+    }
+
+    #[test]
+    fn test_decode_frames() {
+        let signal = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let frames = Protocol::default().decode(signal.as_bytes()).unwrap();
+        assert_tiles(&frames, signal.len());
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    kind: FrameKind::Preamble,
+                    marker: 0..0,
+                    payload: 0..3
+                },
+                Frame {
+                    kind: FrameKind::Packet,
+                    marker: 3..7,
+                    payload: 7..11
+                },
+                Frame {
+                    kind: FrameKind::Message,
+                    marker: 11..25,
+                    payload: 25..25
+                },
+                Frame {
+                    kind: FrameKind::Packet,
+                    marker: 25..29,
+                    payload: 29..30
+                },
+            ]
+        );
+
+        let protocol = Protocol {
+            packet_marker_size: 2,
+            message_marker_size: 3,
+        };
+        let frames = protocol.decode("abxxxcdeff".as_bytes()).unwrap();
+        assert_tiles(&frames, 10);
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.kind, frame.marker.clone()))
+                .collect::<Vec<(FrameKind, Range<usize>)>>(),
+            vec![
+                (FrameKind::Packet, 0..2),
+                (FrameKind::Message, 4..7),
+                (FrameKind::Packet, 7..9),
+            ]
+        );
+
+        for (signal, _, _) in EXAMPLES {
+            assert_tiles(
+                &Protocol::default().decode(signal.as_bytes()).unwrap(),
+                signal.len(),
+            );
+        }
+        assert_eq!(Protocol::default().decode("".as_bytes()).unwrap(), vec![]);
+    }
 }
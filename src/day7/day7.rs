@@ -1,136 +1,357 @@
-use std::fs;
+mod export;
+mod filesystem;
+mod interpreter;
+mod optimizer;
+mod report;
 
-#[derive(Debug)]
-struct File {
-    name: String,
-    size: i32,
-}
+use std::{env, fs, path::Path};
+
+use export::{materialize, to_json};
+use filesystem::FileSystem;
+use interpreter::replay;
+use optimizer::{plan_deletion, Disk};
+use report::{du, tree, ReportOptions, SortOrder};
 
-#[derive(Debug)]
-struct Directory {
-    name: String,
-    parent: Option<usize>,
-    files: Vec<File>,
-    directories: Vec<usize>,
+fn part_1(sizes: &[u64]) -> u64 {
+    sizes.iter().filter(|size| **size <= 100_000).sum()
 }
 
-impl Directory {
-    fn get_sum_size(&self, directories: &Vec<Directory>) -> i32 {
-        let mut sum_size = 0;
-        for file in &self.files {
-            sum_size += file.size;
-        }
-        for directory in &self.directories {
-            if let Some(directory) = directories.get(*directory) {
-                sum_size += directory.get_sum_size(directories);
-            }
-        }
-        return sum_size;
-    }
+fn part_2(sizes: &[u64], disk: &Disk) -> Option<u64> {
+    let space_to_free = disk.space_to_free(sizes[FileSystem::ROOT]);
+
+    sizes
+        .iter()
+        .filter(|size| **size >= space_to_free)
+        .min()
+        .cloned()
 }
 
 fn main() {
     let input = fs::read_to_string("src/day7/input.txt").unwrap();
+    let file_system = match replay(&input) {
+        Ok(file_system) => file_system,
+        Err(error) => {
+            eprintln!("Invalid terminal log: {}", error);
+            return;
+        }
+    };
+    let sizes = file_system.sizes();
 
-    let root = Directory {
-        name: "/".to_string(),
-        parent: None,
-        files: vec![],
-        directories: vec![],
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let parse_size = |prefix: &str| {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix(prefix))
+            .map(|size| size.parse::<u64>().unwrap())
+    };
+    let default_disk = Disk::default();
+    let disk = Disk {
+        capacity: parse_size("--capacity=").unwrap_or(default_disk.capacity),
+        required_free_space: parse_size("--required=").unwrap_or(default_disk.required_free_space),
     };
 
-    let mut directories = vec![];
-    directories.push(root);
-    let mut current_directory: usize = 0;
-
-    input.lines().skip(1).for_each(|line| {
-        if line.starts_with("$") {
-            if line.starts_with("$ cd") {
-                if let Some(dir_name) = line.strip_prefix("$ cd ") {
-                    if dir_name == ".." {
-                        if let Some(cd) = directories.get_mut(current_directory) {
-                            if let Some(parent) = cd.parent {
-                                current_directory = parent;
-                            }
-                        }
-                    } else {
-                        if let Some((i, _)) = directories.iter().enumerate().find(|(_, dir)| {
-                            dir.name == dir_name && dir.parent == Some(current_directory)
-                        }) {
-                            current_directory = i;
-                        }
-                    }
-                }
+    println!("Sum size of small directories: {}", part_1(&sizes));
+
+    if let Some(size) = part_2(&sizes, &disk) {
+        println!("Size of directory to delete: {}", size);
+    }
+
+    let options = ReportOptions {
+        sort: match args.iter().find_map(|arg| arg.strip_prefix("--sort=")) {
+            Some("size") => SortOrder::Size,
+            _ => SortOrder::Name,
+        },
+        max_depth: args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("--max-depth="))
+            .map(|depth| depth.parse().unwrap()),
+        human_readable: !args.iter().any(|arg| arg == "--bytes"),
+    };
+
+    for arg in &args {
+        if let Some(path) = arg.strip_prefix("--ls=") {
+            match file_system.lookup(path) {
+                Some(id) => file_system
+                    .list(id)
+                    .iter()
+                    .for_each(|entry| println!("{}", entry)),
+                None => eprintln!("No such directory: {}", path),
             }
-        } else {
-            if line.starts_with("dir") {
-                if let Some(name) = line.strip_prefix("dir ") {
-                    if let Some(d) = directories
+        } else if let Some(size) = arg.strip_prefix("--over=") {
+            file_system
+                .directories_over(&sizes, size.parse().unwrap())
+                .iter()
+                .for_each(|(path, size)| println!("{} {}", size, path));
+        } else if let Some(count) = arg.strip_prefix("--largest=") {
+            file_system
+                .largest_files(count.parse().unwrap())
+                .iter()
+                .for_each(|(path, size)| println!("{} {}", size, path));
+        } else if arg == "--plan" || arg == "--plan-files" {
+            match plan_deletion(&file_system, &sizes, &disk, arg == "--plan-files") {
+                Ok(plan) => {
+                    plan.paths
                         .iter()
-                        .find(|d| d.name == name && d.parent == Some(current_directory))
-                    {
-                        eprintln!("Directory already exists: {}", d.name);
-                    } else {
-                        directories.push(Directory {
-                            name: name.to_string(),
-                            parent: Some(current_directory),
-                            files: vec![],
-                            directories: vec![],
-                        });
-                        let new_directory_index = directories.len() - 1;
-
-                        if let Some(cd) = directories.get_mut(current_directory) {
-                            cd.directories.push(new_directory_index);
-                        }
-                    }
-                }
-            } else {
-                let parts = line.split(" ").collect::<Vec<&str>>();
-                let size = match parts.get(0) {
-                    Some(s) => (**s).parse().unwrap(),
-                    None => -1,
-                };
-                let name = match parts.get(1) {
-                    Some(s) => s,
-                    None => "UNKNOWN",
-                };
-
-                if let Some(cd) = directories.get_mut(current_directory) {
-                    if let None = cd.files.iter().find(|f| f.name == name) {
-                        cd.files.push(File {
-                            name: name.to_string(),
-                            size: size,
-                        });
-                    } else {
-                        eprintln!("File already exists: {}/{}", cd.name, name);
-                    }
+                        .for_each(|(path, size)| println!("{} {}", size, path));
+                    println!("Total size to delete: {}", plan.total_size);
                 }
+                Err(error) => println!("No deletion plan: {}", error),
             }
+        } else if arg == "--json" {
+            print!("{}", to_json(&file_system, &sizes));
+        } else if let Some(path) = arg.strip_prefix("--json=") {
+            fs::write(path, to_json(&file_system, &sizes)).unwrap();
+        } else if let Some(target) = arg.strip_prefix("--materialize=") {
+            if let Err(error) = materialize(&file_system, Path::new(target)) {
+                eprintln!("Could not materialize into {}: {}", target, error);
+            }
+        } else if arg == "--du" {
+            print!("{}", du(&file_system, &sizes, &options));
+        } else if arg == "--tree" {
+            print!("{}", tree(&file_system, &sizes, &options));
         }
-    });
+    }
+}
 
-    let sum = directories
-        .iter()
-        .map(|d| d.get_sum_size(&directories))
-        .filter(|size| *size <= 100_000)
-        .sum::<i32>();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use interpreter::LogError;
+    use optimizer::{DeletionPlan, PlanError};
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day7/test.txt").unwrap();
+        let file_system = replay(&input).unwrap();
+        assert_eq!(part_1(&file_system.sizes()), 95437);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day7/test.txt").unwrap();
+        let file_system = replay(&input).unwrap();
+        assert_eq!(
+            part_2(&file_system.sizes(), &Disk::default()),
+            Some(24933642)
+        );
+    }
 
-    println!("Sum size of small directories: {}", sum);
+    #[test]
+    fn test_paths() {
+        let input = fs::read_to_string("src/day7/test.txt").unwrap();
+        let file_system = replay(&input).unwrap();
+        let e = file_system.lookup("/a/e").unwrap();
+        assert_eq!(file_system.path(e), "/a/e");
+        assert_eq!(file_system.resolve(e, "../../d"), file_system.lookup("/d"));
+        assert_eq!(file_system.resolve(e, "/"), Some(FileSystem::ROOT));
+        assert_eq!(file_system.lookup("/a/x"), None);
+        assert_eq!(file_system.list(e), vec!["584 i"]);
+        assert_eq!(
+            file_system.list(FileSystem::ROOT),
+            vec!["dir a", "14848514 b.txt", "8504156 c.dat", "dir d"]
+        );
+        assert_eq!(
+            file_system.largest_files(2),
+            vec![
+                ("/b.txt".to_string(), 14848514),
+                ("/c.dat".to_string(), 8504156)
+            ]
+        );
+        assert_eq!(
+            file_system.directories_over(&file_system.sizes(), 20_000_000),
+            vec![("/".to_string(), 48381165), ("/d".to_string(), 24933642)]
+        );
+    }
 
-    let used_space = directories
-        .get(0)
-        .and_then(|d| Some(d.get_sum_size(&directories)))
-        .unwrap_or(0);
-    let free_space = 70_000_000 - used_space;
-    let space_to_free = 30_000_000 - free_space;
+    #[test]
+    fn test_reports() {
+        let input = fs::read_to_string("src/day7/test.txt").unwrap();
+        let file_system = replay(&input).unwrap();
+        let sizes = file_system.sizes();
+        let options = ReportOptions {
+            sort: SortOrder::Size,
+            max_depth: Some(1),
+            human_readable: true,
+        };
+        assert_eq!(
+            du(&file_system, &sizes, &options),
+            "24M\t/d\n93K\t/a\n47M\t/\n"
+        );
+        let options = ReportOptions {
+            sort: SortOrder::Name,
+            max_depth: None,
+            human_readable: false,
+        };
+        let expected = fs::read_to_string("src/day7/test_tree.txt").unwrap();
+        assert_eq!(tree(&file_system, &sizes, &options), expected);
+    }
 
-    if let Some((_, sum)) = directories
-        .iter()
-        .enumerate()
-        .map(|(i, d)| (i, d.get_sum_size(&directories)))
-        .filter(|(_, sum)| *sum >= space_to_free)
-        .min_by(|(_, sum1), (_, sum2)| sum1.cmp(sum2))
+    #[test]
+    fn test_plan_deletion() {
+        let input = fs::read_to_string("src/day7/test.txt").unwrap();
+        let file_system = replay(&input).unwrap();
+        let sizes = file_system.sizes();
+
+        let plan = plan_deletion(&file_system, &sizes, &Disk::default(), false).unwrap();
+        assert_eq!(plan.paths, vec![("/d".to_string(), 24933642)]);
+
+        let plan = plan_deletion(&file_system, &sizes, &Disk::default(), true).unwrap();
+        assert_eq!(plan.paths, vec![("/c.dat".to_string(), 8504156)]);
+
+        let disk = Disk {
+            capacity: 48_500_000,
+            required_free_space: 200_000,
+        };
+        let plan = plan_deletion(&file_system, &sizes, &disk, true).unwrap();
+        assert_eq!(
+            plan,
+            DeletionPlan {
+                paths: vec![("/a/f".to_string(), 29116), ("/a/h.lst".to_string(), 62596)],
+                total_size: 91712
+            }
+        );
+        let plan = plan_deletion(&file_system, &sizes, &disk, false).unwrap();
+        assert_eq!(plan.paths, vec![("/a".to_string(), 94853)]);
+
+        let file_system = replay("$ cd /\n$ ls\n40000000000 x\n30000000000 y\n").unwrap();
+        let sizes = file_system.sizes();
+        let disk = Disk {
+            capacity: 100_000_000_000,
+            required_free_space: 50_000_000_000,
+        };
+        let plan = plan_deletion(&file_system, &sizes, &disk, true).unwrap();
+        assert_eq!(plan.paths, vec![("/y".to_string(), 30_000_000_000)]);
+        let plan = plan_deletion(&file_system, &sizes, &disk, false).unwrap();
+        assert_eq!(plan.paths, vec![("/".to_string(), 70_000_000_000)]);
+        let disk = Disk {
+            capacity: 10_000_000_000,
+            required_free_space: 80_000_000_000,
+        };
+        assert_eq!(
+            plan_deletion(&file_system, &sizes, &disk, true),
+            Err(PlanError::NotEnoughSpace {
+                space_to_free: 80_000_000_000
+            })
+        );
+        assert_eq!(part_2(&sizes, &disk), None);
+
+        let file_system = replay("$ cd /\n$ ls\n60000000 x\n").unwrap();
+        let sizes = file_system.sizes();
+        let plan = plan_deletion(&file_system, &sizes, &Disk::default(), false).unwrap();
+        assert_eq!(plan.paths, vec![("/".to_string(), 60_000_000)]);
+        assert_eq!(part_2(&sizes, &Disk::default()), Some(plan.total_size));
+    }
+
+    #[test]
+    fn test_replay_semantics() {
+        let input = "$ cd ..\n$ ls\ndir a\n1 x\n$ cd a\n$ ls\n2 y\n$ cd /\n$ ls\n1 x\ndir a\n";
+        let file_system = replay(input).unwrap();
+        assert_eq!(file_system.sizes()[FileSystem::ROOT], 3);
+        assert_eq!(
+            file_system.list(file_system.lookup("/a").unwrap()),
+            vec!["2 y"]
+        );
+
+        let file_system = replay("$ cd /a/b\n$ ls\n5 z\n$ cd /\n$ ls\ndir a\n").unwrap();
+        assert_eq!(file_system.sizes()[FileSystem::ROOT], 5);
+    }
+
+    #[test]
+    fn test_replay_errors() {
+        let cases = [
+            (
+                "$ cd /\n$ rm -rf a\n",
+                LogError::UnknownCommand {
+                    line: 2,
+                    command: "rm -rf a".to_string(),
+                },
+            ),
+            (
+                "$ cd\n",
+                LogError::MissingArgument {
+                    line: 1,
+                    command: "cd".to_string(),
+                },
+            ),
+            ("$ cd /\ndir a\n", LogError::OutputWithoutLs { line: 2 }),
+            (
+                "$ ls\nabc x\n",
+                LogError::MalformedEntry {
+                    line: 2,
+                    entry: "abc x".to_string(),
+                },
+            ),
+            (
+                "$ ls\n1 x\n2 x\n",
+                LogError::DuplicateEntry {
+                    line: 3,
+                    path: "/x".to_string(),
+                },
+            ),
+            (
+                "$ ls\n1 x\n$ cd x\n",
+                LogError::NotADirectory {
+                    line: 3,
+                    path: "/x".to_string(),
+                },
+            ),
+            (
+                "$ ls\ndir a\n$ cd b\n",
+                LogError::NoSuchDirectory {
+                    line: 3,
+                    path: "/b".to_string(),
+                },
+            ),
+            (
+                "$ ls\n1 x\n$ ls\n2 x\n",
+                LogError::ConflictingListing {
+                    line: 3,
+                    path: "/".to_string(),
+                },
+            ),
+            (
+                "$ cd a\n$ cd ..\n$ ls\n1 x\n",
+                LogError::ConflictingListing {
+                    line: 3,
+                    path: "/".to_string(),
+                },
+            ),
+        ];
+        for (input, error) in cases {
+            assert_eq!(replay(input).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let file_system = replay("$ cd /\n$ ls\ndir a\n10 \"q\".txt\n$ cd a\n$ ls\n5 b\n").unwrap();
+        let sizes = file_system.sizes();
+        let expected = r#"{
+  "name": "/",
+  "path": "/",
+  "size": 15,
+  "files": [
+    { "name": "\"q\".txt", "size": 10 }
+  ],
+  "directories": [
     {
-        println!("Size of directory to delete: {}", sum);
+      "name": "a",
+      "path": "/a",
+      "size": 5,
+      "files": [
+        { "name": "b", "size": 5 }
+      ],
+      "directories": []
+    }
+  ]
+}
+"#;
+        assert_eq!(to_json(&file_system, &sizes), expected);
+
+        let target = env::temp_dir().join(format!("day7_export_{}", std::process::id()));
+        materialize(&file_system, &target).unwrap();
+        assert_eq!(fs::metadata(target.join("a/b")).unwrap().len(), 5);
+        assert_eq!(fs::metadata(target.join("\"q\".txt")).unwrap().len(), 10);
+        assert!(materialize(&file_system, &target).is_err());
+        fs::remove_dir_all(&target).unwrap();
     }
 }
@@ -0,0 +1,94 @@
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    path::Path,
+};
+
+use super::filesystem::{DirectoryId, FileSystem};
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn directory_json(
+    file_system: &FileSystem,
+    sizes: &[u64],
+    id: DirectoryId,
+    indent: usize,
+) -> String {
+    let directory = file_system.directory(id);
+    let padding = "  ".repeat(indent + 1);
+    let files = directory
+        .files
+        .iter()
+        .map(|(name, size)| {
+            format!(
+                "{}  {{ \"name\": {}, \"size\": {} }}",
+                padding,
+                json_string(name),
+                size
+            )
+        })
+        .collect::<Vec<String>>();
+    let directories = directory
+        .directories
+        .values()
+        .map(|child| {
+            format!(
+                "{}  {}",
+                padding,
+                directory_json(file_system, sizes, *child, indent + 2)
+            )
+        })
+        .collect::<Vec<String>>();
+    let list = |items: Vec<String>| {
+        if items.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n{}]", items.join(",\n"), padding)
+        }
+    };
+    format!(
+        "{{\n{padding}\"name\": {},\n{padding}\"path\": {},\n{padding}\"size\": {},\n{padding}\"files\": {},\n{padding}\"directories\": {}\n{}}}",
+        json_string(&directory.name),
+        json_string(&file_system.path(id)),
+        sizes[id],
+        list(files),
+        list(directories),
+        "  ".repeat(indent),
+    )
+}
+
+pub fn to_json(file_system: &FileSystem, sizes: &[u64]) -> String {
+    format!(
+        "{}\n",
+        directory_json(file_system, sizes, FileSystem::ROOT, 0)
+    )
+}
+
+pub fn materialize(file_system: &FileSystem, target: &Path) -> io::Result<()> {
+    for id in file_system.directory_ids() {
+        let directory_path = target.join(file_system.path(id).trim_start_matches('/'));
+        fs::create_dir_all(&directory_path)?;
+        for (name, size) in &file_system.directory(id).files {
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(directory_path.join(name))?;
+            file.set_len(*size)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+pub type DirectoryId = usize;
+
+#[derive(Debug)]
+pub struct Directory {
+    pub name: String,
+    pub parent: Option<DirectoryId>,
+    pub files: BTreeMap<String, u64>,
+    pub directories: BTreeMap<String, DirectoryId>,
+}
+
+#[derive(Debug)]
+pub struct FileSystem {
+    directories: Vec<Directory>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        FileSystem {
+            directories: vec![Directory {
+                name: "/".to_string(),
+                parent: None,
+                files: BTreeMap::new(),
+                directories: BTreeMap::new(),
+            }],
+        }
+    }
+}
+
+impl FileSystem {
+    pub const ROOT: DirectoryId = 0;
+
+    pub fn directory(&self, id: DirectoryId) -> &Directory {
+        &self.directories[id]
+    }
+
+    pub fn directory_ids(&self) -> impl Iterator<Item = DirectoryId> {
+        0..self.directories.len()
+    }
+
+    pub fn parent(&self, id: DirectoryId) -> DirectoryId {
+        self.directories[id].parent.unwrap_or(FileSystem::ROOT)
+    }
+
+    pub fn resolve(&self, cwd: DirectoryId, path: &str) -> Option<DirectoryId> {
+        let mut current = if path.starts_with('/') {
+            FileSystem::ROOT
+        } else {
+            cwd
+        };
+        for component in path.split('/') {
+            current = match component {
+                "" | "." => current,
+                ".." => self.parent(current),
+                name => *self.directories[current].directories.get(name)?,
+            };
+        }
+        Some(current)
+    }
+
+    pub fn lookup(&self, path: &str) -> Option<DirectoryId> {
+        self.resolve(FileSystem::ROOT, path)
+    }
+
+    pub fn path(&self, id: DirectoryId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.directories[current].parent {
+            names.push(self.directories[current].name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    pub fn file_path(&self, id: DirectoryId, name: &str) -> String {
+        match id {
+            FileSystem::ROOT => format!("/{}", name),
+            _ => format!("{}/{}", self.path(id), name),
+        }
+    }
+
+    pub fn create_directory(&mut self, parent: DirectoryId, name: &str) -> DirectoryId {
+        if let Some(id) = self.directories[parent].directories.get(name) {
+            return *id;
+        }
+        self.directories.push(Directory {
+            name: name.to_string(),
+            parent: Some(parent),
+            files: BTreeMap::new(),
+            directories: BTreeMap::new(),
+        });
+        let id = self.directories.len() - 1;
+        self.directories[parent]
+            .directories
+            .insert(name.to_string(), id);
+        id
+    }
+
+    pub fn add_file(&mut self, directory: DirectoryId, name: &str, size: u64) -> Option<u64> {
+        self.directories[directory]
+            .files
+            .insert(name.to_string(), size)
+    }
+
+    pub fn list(&self, id: DirectoryId) -> Vec<String> {
+        let directory = &self.directories[id];
+        let mut entries = directory
+            .directories
+            .keys()
+            .map(|name| (name, format!("dir {}", name)))
+            .chain(
+                directory
+                    .files
+                    .iter()
+                    .map(|(name, size)| (name, format!("{} {}", size, name))),
+            )
+            .collect::<Vec<(&String, String)>>();
+        entries.sort();
+        entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn sizes(&self) -> Vec<u64> {
+        let mut sizes = self
+            .directories
+            .iter()
+            .map(|directory| directory.files.values().sum())
+            .collect::<Vec<u64>>();
+        // Directories are always created after their parent, so walking the ids backwards
+        // visits every child before its parent.
+        for id in (0..self.directories.len()).rev() {
+            if let Some(parent) = self.directories[id].parent {
+                sizes[parent] += sizes[id];
+            }
+        }
+        sizes
+    }
+
+    pub fn directories_over(&self, sizes: &[u64], size: u64) -> Vec<(String, u64)> {
+        self.directory_ids()
+            .filter(|id| sizes[*id] > size)
+            .map(|id| (self.path(id), sizes[id]))
+            .collect()
+    }
+
+    pub fn largest_files(&self, count: usize) -> Vec<(String, u64)> {
+        let mut files = self
+            .directory_ids()
+            .flat_map(|id| {
+                self.directories[id]
+                    .files
+                    .iter()
+                    .map(move |(name, size)| (self.file_path(id, name), *size))
+            })
+            .collect::<Vec<(String, u64)>>();
+        files.sort_by(|(name1, size1), (name2, size2)| size2.cmp(size1).then(name1.cmp(name2)));
+        files.truncate(count);
+        files
+    }
+}
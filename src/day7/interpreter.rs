@@ -0,0 +1,237 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
+};
+
+use super::filesystem::{DirectoryId, FileSystem};
+
+#[derive(Debug, PartialEq)]
+pub enum LogError {
+    UnknownCommand { line: usize, command: String },
+    MissingArgument { line: usize, command: String },
+    OutputWithoutLs { line: usize },
+    MalformedEntry { line: usize, entry: String },
+    DuplicateEntry { line: usize, path: String },
+    NotADirectory { line: usize, path: String },
+    NoSuchDirectory { line: usize, path: String },
+    ConflictingListing { line: usize, path: String },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogError::UnknownCommand { line, command } => {
+                write!(f, "line {line}: unknown command `{command}`")
+            }
+            LogError::MissingArgument { line, command } => {
+                write!(f, "line {line}: `{command}` needs an argument")
+            }
+            LogError::OutputWithoutLs { line } => {
+                write!(f, "line {line}: output without a preceding `ls`")
+            }
+            LogError::MalformedEntry { line, entry } => {
+                write!(f, "line {line}: malformed listing entry `{entry}`")
+            }
+            LogError::DuplicateEntry { line, path } => {
+                write!(f, "line {line}: {path} is listed more than once")
+            }
+            LogError::NotADirectory { line, path } => {
+                write!(f, "line {line}: {path} is not a directory")
+            }
+            LogError::NoSuchDirectory { line, path } => {
+                write!(f, "line {line}: {path} does not exist")
+            }
+            LogError::ConflictingListing { line, path } => {
+                write!(
+                    f,
+                    "line {line}: listing of {path} conflicts with an earlier one"
+                )
+            }
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Entry {
+    Directory,
+    File(u64),
+}
+
+struct Listing {
+    directory: DirectoryId,
+    line: usize,
+    entries: BTreeMap<String, Entry>,
+}
+
+struct Interpreter {
+    file_system: FileSystem,
+    current_directory: DirectoryId,
+    listed: HashSet<DirectoryId>,
+    listing: Option<Listing>,
+}
+
+impl Interpreter {
+    fn cd(&mut self, line: usize, path: &str) -> Result<(), LogError> {
+        let mut current = if path.starts_with('/') {
+            FileSystem::ROOT
+        } else {
+            self.current_directory
+        };
+        for component in path.split('/') {
+            current = match component {
+                "" | "." => current,
+                ".." => self.file_system.parent(current),
+                name => {
+                    let directory = self.file_system.directory(current);
+                    let path = self.file_system.file_path(current, name);
+                    if directory.files.contains_key(name) {
+                        return Err(LogError::NotADirectory { line, path });
+                    }
+                    if self.listed.contains(&current) && !directory.directories.contains_key(name) {
+                        return Err(LogError::NoSuchDirectory { line, path });
+                    }
+                    self.file_system.create_directory(current, name)
+                }
+            };
+        }
+        self.current_directory = current;
+        Ok(())
+    }
+
+    fn add_entry(&mut self, line: usize, output: &str) -> Result<(), LogError> {
+        let listing = self
+            .listing
+            .as_mut()
+            .ok_or(LogError::OutputWithoutLs { line })?;
+        let malformed = || LogError::MalformedEntry {
+            line,
+            entry: output.to_string(),
+        };
+        let (kind, name) = output.split_once(' ').ok_or_else(malformed)?;
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return Err(malformed());
+        }
+        let entry = match kind {
+            "dir" => Entry::Directory,
+            size => Entry::File(size.parse::<u64>().map_err(|_| malformed())?),
+        };
+        if listing.entries.insert(name.to_string(), entry).is_some() {
+            return Err(LogError::DuplicateEntry {
+                line,
+                path: self.file_system.file_path(listing.directory, name),
+            });
+        }
+        Ok(())
+    }
+
+    fn finish_listing(&mut self) -> Result<(), LogError> {
+        let Some(listing) = self.listing.take() else {
+            return Ok(());
+        };
+        let id = listing.directory;
+        let directory = self.file_system.directory(id);
+        let conflict = || LogError::ConflictingListing {
+            line: listing.line,
+            path: self.file_system.path(id),
+        };
+
+        if self.listed.contains(&id) {
+            let directories = listing
+                .entries
+                .iter()
+                .filter(|(_, entry)| **entry == Entry::Directory)
+                .map(|(name, _)| name)
+                .collect::<BTreeSet<&String>>();
+            let files = listing
+                .entries
+                .iter()
+                .filter_map(|(name, entry)| match entry {
+                    Entry::File(size) => Some((name, size)),
+                    Entry::Directory => None,
+                })
+                .collect::<BTreeMap<&String, &u64>>();
+            if directories != directory.directories.keys().collect()
+                || files != directory.files.iter().collect()
+            {
+                return Err(conflict());
+            }
+            return Ok(());
+        }
+
+        for (name, entry) in &listing.entries {
+            let conflicting = match entry {
+                Entry::Directory => directory.files.contains_key(name),
+                Entry::File(_) => directory.directories.contains_key(name),
+            };
+            if conflicting {
+                return Err(conflict());
+            }
+        }
+        // Directories entered with `cd` before the first `ls` must show up in the listing.
+        if directory
+            .directories
+            .keys()
+            .any(|name| listing.entries.get(name) != Some(&Entry::Directory))
+        {
+            return Err(conflict());
+        }
+
+        for (name, entry) in &listing.entries {
+            match entry {
+                Entry::Directory => {
+                    self.file_system.create_directory(id, name);
+                }
+                Entry::File(size) => {
+                    self.file_system.add_file(id, name, *size);
+                }
+            }
+        }
+        self.listed.insert(id);
+        Ok(())
+    }
+
+    fn execute(&mut self, line: usize, command: &str) -> Result<(), LogError> {
+        self.finish_listing()?;
+        let (name, argument) = match command.split_once(' ') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (command, None),
+        };
+        match (name, argument) {
+            ("cd", Some(path)) if !path.is_empty() => self.cd(line, path),
+            ("cd", _) => Err(LogError::MissingArgument {
+                line,
+                command: command.to_string(),
+            }),
+            ("ls", None) => {
+                self.listing = Some(Listing {
+                    directory: self.current_directory,
+                    line,
+                    entries: BTreeMap::new(),
+                });
+                Ok(())
+            }
+            _ => Err(LogError::UnknownCommand {
+                line,
+                command: command.to_string(),
+            }),
+        }
+    }
+}
+
+pub fn replay(input: &str) -> Result<FileSystem, LogError> {
+    let mut interpreter = Interpreter {
+        file_system: FileSystem::default(),
+        current_directory: FileSystem::ROOT,
+        listed: HashSet::new(),
+        listing: None,
+    };
+    for (i, line) in input.lines().enumerate() {
+        match line.strip_prefix('$') {
+            Some(command) => interpreter.execute(i + 1, command.trim())?,
+            None if line.trim().is_empty() => {}
+            None => interpreter.add_entry(i + 1, line)?,
+        }
+    }
+    interpreter.finish_listing()?;
+    Ok(interpreter.file_system)
+}
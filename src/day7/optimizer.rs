@@ -0,0 +1,194 @@
+use std::fmt;
+
+use super::filesystem::{DirectoryId, FileSystem};
+
+pub const NODE_LIMIT: usize = 10_000_000;
+
+pub struct Disk {
+    pub capacity: u64,
+    pub required_free_space: u64,
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Disk {
+            capacity: 70_000_000,
+            required_free_space: 30_000_000,
+        }
+    }
+}
+
+impl Disk {
+    pub fn space_to_free(&self, used_space: u64) -> u64 {
+        let free_space = self.capacity.saturating_sub(used_space);
+        self.required_free_space.saturating_sub(free_space)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DeletionPlan {
+    pub paths: Vec<(String, u64)>,
+    pub total_size: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    NotEnoughSpace { space_to_free: u64 },
+    TooManyNodes { limit: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::NotEnoughSpace { space_to_free } => {
+                write!(f, "{space_to_free} bytes cannot be freed")
+            }
+            PlanError::TooManyNodes { limit } => {
+                write!(f, "gave up after exploring {limit} partial plans")
+            }
+        }
+    }
+}
+
+struct Candidate {
+    path: String,
+    size: u64,
+    end: usize,
+}
+
+// Candidates are listed in pre-order from `/` with the largest entries first, and `end` points
+// past a candidate's descendants so deleting it skips everything inside it. `reachable[i]` is the
+// most that can still be freed from candidate `i` onwards, which bounds every branch.
+struct Search {
+    candidates: Vec<Candidate>,
+    reachable: Vec<u64>,
+    target: u64,
+    chosen: Vec<usize>,
+    best: Option<(u64, Vec<usize>)>,
+    nodes: usize,
+}
+
+impl Search {
+    fn add_directory(
+        &mut self,
+        file_system: &FileSystem,
+        sizes: &[u64],
+        id: DirectoryId,
+        include_files: bool,
+    ) {
+        let index = self.candidates.len();
+        self.candidates.push(Candidate {
+            path: file_system.path(id),
+            size: sizes[id],
+            end: index + 1,
+        });
+
+        let directory = file_system.directory(id);
+        let mut entries = directory
+            .directories
+            .values()
+            .map(|child| (sizes[*child], Some(*child), file_system.path(*child)))
+            .collect::<Vec<(u64, Option<DirectoryId>, String)>>();
+        if include_files {
+            entries.extend(
+                directory
+                    .files
+                    .iter()
+                    .map(|(name, size)| (*size, None, file_system.file_path(id, name))),
+            );
+        }
+        entries.sort_by(|(size1, _, path1), (size2, _, path2)| {
+            size2.cmp(size1).then(path1.cmp(path2))
+        });
+        for (size, child, path) in entries {
+            match child {
+                Some(child) => self.add_directory(file_system, sizes, child, include_files),
+                None => self.candidates.push(Candidate {
+                    path,
+                    size,
+                    end: self.candidates.len() + 1,
+                }),
+            }
+        }
+        self.candidates[index].end = self.candidates.len();
+    }
+
+    fn search(&mut self, index: usize, sum: u64) -> Result<(), PlanError> {
+        self.nodes += 1;
+        if self.nodes > NODE_LIMIT {
+            return Err(PlanError::TooManyNodes { limit: NODE_LIMIT });
+        }
+        if sum >= self.target {
+            if self.best.as_ref().is_none_or(|(best, _)| sum < *best) {
+                self.best = Some((sum, self.chosen.clone()));
+            }
+            return Ok(());
+        }
+        if index == self.candidates.len()
+            || sum + self.reachable[index] < self.target
+            || self.best.as_ref().is_some_and(|(best, _)| sum >= *best)
+        {
+            return Ok(());
+        }
+
+        self.chosen.push(index);
+        let candidate = &self.candidates[index];
+        self.search(candidate.end, sum + candidate.size)?;
+        self.chosen.pop();
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|(best, _)| *best == self.target)
+        {
+            return Ok(());
+        }
+        self.search(index + 1, sum)
+    }
+}
+
+pub fn plan_deletion(
+    file_system: &FileSystem,
+    sizes: &[u64],
+    disk: &Disk,
+    include_files: bool,
+) -> Result<DeletionPlan, PlanError> {
+    let space_to_free = disk.space_to_free(sizes[FileSystem::ROOT]);
+    if space_to_free == 0 {
+        return Ok(DeletionPlan {
+            paths: vec![],
+            total_size: 0,
+        });
+    }
+
+    let mut search = Search {
+        candidates: vec![],
+        reachable: vec![],
+        target: space_to_free,
+        chosen: vec![],
+        best: None,
+        nodes: 0,
+    };
+    search.add_directory(file_system, sizes, FileSystem::ROOT, include_files);
+    search.reachable = vec![0; search.candidates.len() + 1];
+    for (i, candidate) in search.candidates.iter().enumerate().rev() {
+        search.reachable[i] =
+            (candidate.size + search.reachable[candidate.end]).max(search.reachable[i + 1]);
+    }
+    search.search(0, 0)?;
+
+    let (_, chosen) = search
+        .best
+        .ok_or(PlanError::NotEnoughSpace { space_to_free })?;
+    let mut paths = chosen
+        .into_iter()
+        .map(|i| {
+            let candidate = &search.candidates[i];
+            (candidate.path.clone(), candidate.size)
+        })
+        .collect::<Vec<(String, u64)>>();
+    paths.sort();
+    Ok(DeletionPlan {
+        total_size: paths.iter().map(|(_, size)| size).sum(),
+        paths,
+    })
+}
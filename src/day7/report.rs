@@ -0,0 +1,193 @@
+use super::filesystem::{DirectoryId, FileSystem};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Name,
+    Size,
+}
+
+pub struct ReportOptions {
+    pub sort: SortOrder,
+    pub max_depth: Option<usize>,
+    pub human_readable: bool,
+}
+
+impl ReportOptions {
+    fn format_size(&self, size: u64) -> String {
+        if self.human_readable {
+            human_size(size)
+        } else {
+            size.to_string()
+        }
+    }
+
+    fn within_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+}
+
+enum Entry<'a> {
+    Directory(&'a str, DirectoryId),
+    File(&'a str),
+}
+
+fn human_size(size: u64) -> String {
+    let units = ["K", "M", "G", "T", "P"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let rounded = (value * 10.0).ceil() / 10.0;
+    if rounded < 10.0 {
+        format!("{:.1}{}", rounded, units[unit])
+    } else {
+        format!("{}{}", value.ceil(), units[unit])
+    }
+}
+
+fn entries<'a>(
+    file_system: &'a FileSystem,
+    sizes: &[u64],
+    id: DirectoryId,
+    sort: SortOrder,
+    include_files: bool,
+) -> Vec<(Entry<'a>, u64)> {
+    let directory = file_system.directory(id);
+    let mut entries = directory
+        .directories
+        .iter()
+        .map(|(name, child)| (Entry::Directory(name, *child), sizes[*child]))
+        .collect::<Vec<(Entry, u64)>>();
+    if include_files {
+        entries.extend(
+            directory
+                .files
+                .iter()
+                .map(|(name, size)| (Entry::File(name), *size)),
+        );
+    }
+    let name = |entry: &Entry<'a>| match entry {
+        Entry::Directory(name, _) | Entry::File(name) => *name,
+    };
+    entries.sort_by(|(entry1, size1), (entry2, size2)| match sort {
+        SortOrder::Name => name(entry1).cmp(name(entry2)),
+        SortOrder::Size => size2.cmp(size1).then(name(entry1).cmp(name(entry2))),
+    });
+    entries
+}
+
+struct Report<'a> {
+    file_system: &'a FileSystem,
+    sizes: &'a [u64],
+    options: &'a ReportOptions,
+    lines: Vec<String>,
+    directory_count: usize,
+    file_count: usize,
+}
+
+impl<'a> Report<'a> {
+    fn new(
+        file_system: &'a FileSystem,
+        sizes: &'a [u64],
+        options: &'a ReportOptions,
+    ) -> Report<'a> {
+        Report {
+            file_system,
+            sizes,
+            options,
+            lines: vec![],
+            directory_count: 0,
+            file_count: 0,
+        }
+    }
+
+    fn entries(&self, id: DirectoryId, include_files: bool) -> Vec<(Entry<'a>, u64)> {
+        entries(
+            self.file_system,
+            self.sizes,
+            id,
+            self.options.sort,
+            include_files,
+        )
+    }
+
+    fn du_directory(&mut self, id: DirectoryId, depth: usize) {
+        for (entry, _) in self.entries(id, false) {
+            if let Entry::Directory(_, child) = entry {
+                self.du_directory(child, depth + 1);
+            }
+        }
+        if self.options.within_depth(depth) {
+            self.lines.push(format!(
+                "{}\t{}",
+                self.options.format_size(self.sizes[id]),
+                self.file_system.path(id)
+            ));
+        }
+    }
+
+    fn tree_directory(&mut self, id: DirectoryId, prefix: &str, depth: usize) {
+        if !self.options.within_depth(depth) {
+            return;
+        }
+        let entries = self.entries(id, true);
+        let count = entries.len();
+        for (i, (entry, size)) in entries.into_iter().enumerate() {
+            let (branch, indent) = if i + 1 == count {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let name = match entry {
+                Entry::Directory(name, _) | Entry::File(name) => name,
+            };
+            self.lines.push(format!(
+                "{}{}[{}]  {}",
+                prefix,
+                branch,
+                self.options.format_size(size),
+                name
+            ));
+            match entry {
+                Entry::Directory(_, child) => {
+                    self.directory_count += 1;
+                    self.tree_directory(child, &format!("{}{}", prefix, indent), depth + 1);
+                }
+                Entry::File(_) => self.file_count += 1,
+            }
+        }
+    }
+
+    fn output(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+}
+
+pub fn du(file_system: &FileSystem, sizes: &[u64], options: &ReportOptions) -> String {
+    let mut report = Report::new(file_system, sizes, options);
+    report.du_directory(FileSystem::ROOT, 0);
+    report.output()
+}
+
+pub fn tree(file_system: &FileSystem, sizes: &[u64], options: &ReportOptions) -> String {
+    let mut report = Report::new(file_system, sizes, options);
+    report.lines.push(format!(
+        "[{}]  /",
+        options.format_size(sizes[FileSystem::ROOT])
+    ));
+    report.tree_directory(FileSystem::ROOT, "", 1);
+    report.lines.push(String::new());
+    report.lines.push(format!(
+        "{} directories, {} files",
+        report.directory_count, report.file_count
+    ));
+    report.output()
+}
@@ -1,141 +1,482 @@
-use std::{collections::HashSet, fs};
+mod heatmap;
+mod sight;
 
-#[derive(PartialEq, Clone, Copy, Eq, Hash)]
+use std::{env, fmt, fs};
+
+use heatmap::{annotated_view, scenic_heatmap, visibility_mask};
+use sight::{seen_by, visible_from, Observer};
+
+#[derive(PartialEq, Clone, Copy, Eq, Hash, Debug)]
 struct Tree {
     x: i32,
     y: i32,
     size: i32,
 }
 
-fn column(forest: &Vec<Vec<Tree>>, index: usize) -> Option<Vec<Tree>> {
-    let mut result: Vec<Tree> = vec![];
-    for row in forest {
-        if let Some(c) = row.get(index) {
-            result.push(c.clone());
-        } else {
-            return None;
+#[derive(Debug, PartialEq)]
+enum ForestError {
+    InvalidHeight {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ForestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForestError::InvalidHeight {
+                line,
+                column,
+                value,
+            } => write!(f, "line {line}, column {column}: invalid height `{value}`"),
+            ForestError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} cells, found {found}"),
         }
     }
-    return Some(result);
 }
 
-fn get_visible_trees<'a>(trees: impl Iterator<Item = &'a Tree>) -> HashSet<Tree> {
-    let mut visible_trees = HashSet::new();
-    let mut max_height: Option<i32> = None;
-    for tree in trees {
-        match max_height {
-            Some(mh) => {
-                if tree.size > mh {
-                    max_height = Some(tree.size);
-                    visible_trees.insert(tree.clone());
-                }
-            }
-            None => {
-                max_height = Some(tree.size);
-                visible_trees.insert(tree.clone());
-            }
+#[derive(PartialEq, Clone, Copy, Default, Debug)]
+struct ViewingDistances {
+    up: usize,
+    down: usize,
+    left: usize,
+    right: usize,
+}
+
+impl ViewingDistances {
+    fn scenic_score(&self) -> usize {
+        self.up * self.down * self.left * self.right
+    }
+}
+
+struct ForestAnalysis {
+    visible: Vec<Vec<bool>>,
+    distances: Vec<Vec<ViewingDistances>>,
+    scenic_scores: Vec<Vec<usize>>,
+}
+
+impl ForestAnalysis {
+    fn visible_count(&self) -> usize {
+        self.visible
+            .iter()
+            .flatten()
+            .filter(|visible| **visible)
+            .count()
+    }
+
+    fn best_location(&self) -> Option<(usize, usize, usize)> {
+        self.scenic_scores
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, score)| (x, y, *score)))
+            .max_by(|(x1, y1, score1), (x2, y2, score2)| {
+                score1.cmp(score2).then((y2, x2).cmp(&(y1, x1)))
+            })
+    }
+}
+
+fn get_tree(forest: &[Vec<Option<Tree>>], x: i32, y: i32) -> Option<&Tree> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    forest
+        .get(y as usize)
+        .and_then(|row| row.get(x as usize))
+        .and_then(|tree| tree.as_ref())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HeightFormat {
+    Digits,
+    Separated,
+}
+
+impl HeightFormat {
+    // A map is read as separated heights when any row holds a comma or whitespace.
+    fn detect(input: &str) -> HeightFormat {
+        match input.lines().any(|line| {
+            line.trim()
+                .contains(|c: char| c == ',' || c.is_whitespace())
+        }) {
+            true => HeightFormat::Separated,
+            false => HeightFormat::Digits,
         }
     }
-    return visible_trees;
 }
 
-fn get_tree<'a>(forest: &'a Vec<Vec<Tree>>, x: usize, y: usize) -> Option<&'a Tree> {
-    forest.get(y).and_then(|row| row.get(x))
+fn parse_heights(line: &str, format: HeightFormat) -> Vec<String> {
+    match format {
+        HeightFormat::Digits => line.chars().map(|c| c.to_string()).collect(),
+        HeightFormat::Separated if line.contains(',') => line
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .collect(),
+        HeightFormat::Separated => line
+            .split_whitespace()
+            .map(|value| value.to_string())
+            .collect(),
+    }
 }
 
-fn main() {
-    let input = fs::read_to_string("src/day8/input.txt").unwrap();
-    let forest = input
+// Heights are either single digits, or separated by commas or whitespace. With `holes`, short
+// rows and empty comma separated fields become holes instead of errors.
+fn parse_forest(
+    input: &str,
+    format: HeightFormat,
+    holes: bool,
+) -> Result<Vec<Vec<Option<Tree>>>, ForestError> {
+    let rows = input
         .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, c)| Tree {
+        .map(|line| parse_heights(line.trim(), format))
+        .collect::<Vec<Vec<String>>>();
+    let width = match holes {
+        true => rows.iter().map(|row| row.len()).max().unwrap_or(0),
+        false => rows.first().map_or(0, |row| row.len()),
+    };
+
+    let mut forest = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        if !holes && row.len() != width {
+            return Err(ForestError::RaggedRow {
+                line: y + 1,
+                expected: width,
+                found: row.len(),
+            });
+        }
+        let trees = (0..width)
+            .map(|x| {
+                let size = match row.get(x).map(|value| value.as_str()) {
+                    None | Some("") if holes => return Ok(None),
+                    value => value
+                        .and_then(|value| value.parse::<i32>().ok())
+                        .ok_or_else(|| ForestError::InvalidHeight {
+                            line: y + 1,
+                            column: x + 1,
+                            value: value.unwrap_or_default().to_string(),
+                        })?,
+                };
+                Ok(Some(Tree {
                     x: x as i32,
                     y: y as i32,
-                    size: c.to_digit(10).unwrap() as i32,
-                })
-                .collect::<Vec<Tree>>()
+                    size,
+                }))
+            })
+            .collect::<Result<Vec<Option<Tree>>, ForestError>>()?;
+        forest.push(trees);
+    }
+    Ok(forest)
+}
+
+// For every tree in sweep order, returns whether it is visible from the start of the sweep
+// and how far it can see back towards it. The stack keeps the indices of trees that are not
+// yet blocked, with strictly decreasing heights. Holes neither block nor see anything.
+fn sweep(heights: impl Iterator<Item = Option<i32>>) -> Vec<(bool, usize)> {
+    let mut stack: Vec<(usize, i32)> = vec![];
+    heights
+        .enumerate()
+        .map(|(i, height)| {
+            let Some(height) = height else {
+                return (false, 0);
+            };
+            while stack.last().is_some_and(|(_, other)| *other < height) {
+                stack.pop();
+            }
+            let result = match stack.last() {
+                Some((j, _)) => (false, i - j),
+                None => (true, i),
+            };
+            stack.push((i, height));
+            result
         })
-        .collect::<Vec<Vec<Tree>>>();
+        .collect()
+}
 
+fn analyse_forest(forest: &[Vec<Option<Tree>>]) -> ForestAnalysis {
     let row_count = forest.len();
-    let column_count = match forest.first() {
-        Some(f) => f.len(),
-        None => 0,
+    let column_count = forest.first().map_or(0, |row| row.len());
+    let mut visible = vec![vec![false; column_count]; row_count];
+    let mut distances = vec![vec![ViewingDistances::default(); column_count]; row_count];
+
+    for (y, row) in forest.iter().enumerate() {
+        let from_left = sweep(row.iter().map(|tree| tree.map(|tree| tree.size)));
+        let from_right = sweep(row.iter().rev().map(|tree| tree.map(|tree| tree.size)));
+        for x in 0..column_count {
+            let (left_visible, left) = from_left[x];
+            let (right_visible, right) = from_right[column_count - 1 - x];
+            visible[y][x] |= left_visible || right_visible;
+            distances[y][x].left = left;
+            distances[y][x].right = right;
+        }
+    }
+
+    for x in 0..column_count {
+        let from_top = sweep((0..row_count).map(|y| forest[y][x].map(|tree| tree.size)));
+        let from_bottom = sweep(
+            (0..row_count)
+                .rev()
+                .map(|y| forest[y][x].map(|tree| tree.size)),
+        );
+        for y in 0..row_count {
+            let (top_visible, up) = from_top[y];
+            let (bottom_visible, down) = from_bottom[row_count - 1 - y];
+            visible[y][x] |= top_visible || bottom_visible;
+            distances[y][x].up = up;
+            distances[y][x].down = down;
+        }
+    }
+
+    let scenic_scores = distances
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|distances| distances.scenic_score())
+                .collect()
+        })
+        .collect();
+
+    ForestAnalysis {
+        visible,
+        distances,
+        scenic_scores,
+    }
+}
+
+fn parse_coordinates(value: &str) -> Vec<i32> {
+    value
+        .split(',')
+        .map(|value| value.trim().parse::<i32>().unwrap())
+        .collect()
+}
+
+fn main() {
+    let input = fs::read_to_string("src/day8/input.txt").unwrap();
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let format = if args.iter().any(|arg| arg == "--separated") {
+        HeightFormat::Separated
+    } else if args.iter().any(|arg| arg == "--digits") {
+        HeightFormat::Digits
+    } else {
+        HeightFormat::detect(&input)
     };
+    let forest = match parse_forest(&input, format, args.iter().any(|arg| arg == "--holes")) {
+        Ok(forest) => forest,
+        Err(error) => {
+            eprintln!("Invalid height map: {}", error);
+            return;
+        }
+    };
+    let analysis = analyse_forest(&forest);
 
-    let mut visible_trees = HashSet::new();
+    println!("Visible tree count: {}", analysis.visible_count());
 
-    for row in forest.iter() {
-        visible_trees.extend(get_visible_trees(row.iter()));
-        visible_trees.extend(get_visible_trees(row.iter().rev()));
+    if let Some((x, y, score)) = analysis.best_location() {
+        let distances = analysis.distances[y][x];
+        println!("Highest scenic score: {}", score);
+        if let Some(tree) = forest[y][x] {
+            println!(
+                "Best tree at ({}, {}) with height {}: up {}, down {}, left {}, right {}",
+                x, y, tree.size, distances.up, distances.down, distances.left, distances.right
+            );
+        }
     }
 
-    for ci in 0..column_count {
-        if let Some(column) = &column(&forest, ci) {
-            visible_trees.extend(get_visible_trees(column.iter()));
-            visible_trees.extend(get_visible_trees(column.iter().rev()));
+    for arg in args {
+        if let Some(path) = arg.strip_prefix("--heatmap=") {
+            fs::write(path, scenic_heatmap(&analysis)).unwrap();
+        } else if let Some(path) = arg.strip_prefix("--visibility=") {
+            fs::write(path, visibility_mask(&analysis)).unwrap();
+        } else if let Some(observer) = arg.strip_prefix("--observer=") {
+            let values = parse_coordinates(observer);
+            let observer = Observer {
+                x: values[0],
+                y: values[1],
+                eye_height: values.get(2).copied().unwrap_or(0),
+            };
+            println!(
+                "Trees visible from ({}, {}): {}",
+                observer.x,
+                observer.y,
+                visible_from(&forest, observer).len()
+            );
+        } else if let Some(target) = arg.strip_prefix("--target=") {
+            let values = parse_coordinates(target);
+            println!(
+                "Trees that can see ({}, {}): {}",
+                values[0],
+                values[1],
+                seen_by(
+                    &forest,
+                    (values[0], values[1]),
+                    values.get(2).copied().unwrap_or(0)
+                )
+                .len()
+            );
+        } else if arg == "--annotate" {
+            print!("{}", annotated_view(&forest, &analysis));
         }
     }
+}
 
-    println!("Visible tree count: {}", visible_trees.len());
-
-    let mut max_scenic_score = -1;
-
-    for y in 1..row_count - 1 {
-        for x in 1..column_count - 1 {
-            if let Some(tree) = get_tree(&forest, x, y) {
-                
-                let mut dist_right = 0;
-                for dx in x + 1..column_count {
-                    if let Some(other_tree) = get_tree(&forest, dx, y) {
-                        dist_right += 1;
-                        if other_tree.size >= tree.size {
-                            break;
-                        }
-                    }
-                }
-
-                let mut dist_down = 0;
-                for dy in y + 1..row_count {
-                    if let Some(other_tree) = get_tree(&forest, x, dy) {
-                        dist_down += 1;
-                        if other_tree.size >= tree.size {
-                            break;
-                        }
-                    }
-                }
-
-                let mut dist_left = 0;
-                for dx in (0..x).rev() {
-                    if let Some(other_tree) = get_tree(&forest, dx, y) {
-                        dist_left += 1;
-                        if other_tree.size >= tree.size {
-                            break;
-                        }
-                    }
-                }
-
-                let mut dist_up = 0;
-                for dy in (0..y).rev() {
-                    if let Some(other_tree) = get_tree(&forest, x, dy) {
-                        dist_up += 1;
-                        if other_tree.size >= tree.size {
-                            break;
-                        }
-                    }
-                }
-
-                let scenic_score = dist_right * dist_down * dist_left * dist_up;
-
-                if scenic_score > max_scenic_score {
-                    max_scenic_score = scenic_score;
-                }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day8/test.txt").unwrap();
+        let analysis = analyse_forest(&parse_forest(&input, HeightFormat::Digits, false).unwrap());
+        assert_eq!(analysis.visible_count(), 21);
+        assert!(analysis.visible[1][1]);
+        assert!(!analysis.visible[1][3]);
+        assert!(!analysis.visible[2][2]);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day8/test.txt").unwrap();
+        let analysis = analyse_forest(&parse_forest(&input, HeightFormat::Digits, false).unwrap());
+        assert_eq!(analysis.best_location(), Some((2, 3, 8)));
+        assert_eq!(
+            analysis.distances[1][2],
+            ViewingDistances {
+                up: 1,
+                down: 2,
+                left: 1,
+                right: 2
             }
-        }
+        );
+        assert_eq!(analysis.scenic_scores[1][2], 4);
+        assert_eq!(analysis.scenic_scores[0][0], 0);
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let input = fs::read_to_string("src/day8/test.txt").unwrap();
+        let forest = parse_forest(&input, HeightFormat::Digits, false).unwrap();
+        let analysis = analyse_forest(&forest);
+
+        let heatmap = scenic_heatmap(&analysis);
+        let header = b"P5\n5 5\n255\n";
+        assert_eq!(&heatmap[..header.len()], header);
+        assert_eq!(
+            &heatmap[header.len()..],
+            &[
+                0, 0, 0, 0, 0, 0, 31, 127, 31, 0, 0, 191, 31, 63, 0, 0, 31, 255, 95, 0, 0, 0, 0, 0,
+                0
+            ]
+        );
+
+        let mask = visibility_mask(&analysis);
+        assert_eq!(mask.len(), header.len() + 25);
+        assert_eq!(mask.iter().filter(|pixel| **pixel == 255).count(), 21);
+
+        assert_eq!(
+            annotated_view(&forest, &analysis),
+            "30373\n255.2\n65.32\n3.X.9\n35390\nX: best treehouse at (2, 3), score 8\n"
+        );
     }
 
-    println!("Highest scenic score: {}", max_scenic_score);
+    #[test]
+    fn test_line_of_sight() {
+        assert_eq!(
+            sight::line((0, 0), (4, 2)),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            sight::line((2, 3), (2, 0)),
+            vec![(2, 3), (2, 2), (2, 1), (2, 0)]
+        );
+
+        let forest = parse_forest("30503\n", HeightFormat::Digits, false).unwrap();
+        let observer = |eye_height| Observer {
+            x: 0,
+            y: 0,
+            eye_height,
+        };
+        assert!(sight::can_see(&forest, observer(0), (2, 0)));
+        assert!(!sight::can_see(&forest, observer(0), (4, 0)));
+        assert!(!sight::can_see(&forest, observer(3), (4, 0)));
+        assert!(sight::can_see(&forest, observer(5), (4, 0)));
+        assert!(sight::can_see(&forest, observer(i32::MAX), (4, 0)));
+        assert!(!sight::can_see(&forest, observer(0), (5, 0)));
+        assert!(sight::can_see(&forest, observer(0), (0, 0)));
+
+        let input = fs::read_to_string("src/day8/test.txt").unwrap();
+        let forest = parse_forest(&input, HeightFormat::Digits, false).unwrap();
+        let visible = visible_from(&forest, observer(0));
+        assert!(visible.contains(get_tree(&forest, 1, 1).unwrap()));
+        assert!(!visible.contains(get_tree(&forest, 4, 4).unwrap()));
+        assert_eq!(seen_by(&forest, (2, 2), 0).len(), 11);
+    }
+
+    #[test]
+    fn test_height_map() {
+        let forest = parse_forest("12, 7 ,300\n-4,0,15\n", HeightFormat::Separated, false).unwrap();
+        assert_eq!(
+            forest[0]
+                .iter()
+                .map(|tree| tree.unwrap().size)
+                .collect::<Vec<i32>>(),
+            vec![12, 7, 300]
+        );
+        assert_eq!(get_tree(&forest, 0, 1).unwrap().size, -4);
+        assert_eq!(
+            HeightFormat::detect("12, 7 ,300\n-4,0,15\n"),
+            HeightFormat::Separated
+        );
+
+        let forest = parse_forest("12\n300\n", HeightFormat::Separated, false).unwrap();
+        assert_eq!(
+            forest.concat(),
+            vec![
+                Some(Tree {
+                    x: 0,
+                    y: 0,
+                    size: 12
+                }),
+                Some(Tree {
+                    x: 0,
+                    y: 1,
+                    size: 300
+                })
+            ]
+        );
+        assert_eq!(HeightFormat::detect("12\n300\n"), HeightFormat::Digits);
+
+        assert_eq!(
+            parse_forest("10 20 30\n40\n", HeightFormat::Separated, false),
+            Err(ForestError::RaggedRow {
+                line: 2,
+                expected: 3,
+                found: 1
+            })
+        );
+        assert_eq!(
+            parse_forest("1,x\n", HeightFormat::Separated, false),
+            Err(ForestError::InvalidHeight {
+                line: 1,
+                column: 2,
+                value: "x".to_string()
+            })
+        );
+        assert!(parse_forest("1,,3\n", HeightFormat::Separated, false).is_err());
+        let forest = parse_forest("-2147483648 0\n", HeightFormat::Separated, false).unwrap();
+        assert_eq!(get_tree(&forest, 0, 0).unwrap().size, i32::MIN);
+
+        let forest = parse_forest("10 20 30\n40\n5,,60\n", HeightFormat::Separated, true).unwrap();
+        assert!(forest[1][1].is_none() && forest[1][2].is_none() && forest[2][1].is_none());
+        let analysis = analyse_forest(&forest);
+        assert_eq!(analysis.visible_count(), 6);
+        assert_eq!(analysis.distances[1][1], ViewingDistances::default());
+        assert_eq!(get_tree(&forest, 1, 1), None);
+    }
 }
@@ -0,0 +1,63 @@
+use super::{ForestAnalysis, Tree};
+
+fn pgm(width: usize, height: usize, pixels: impl Iterator<Item = u8>) -> Vec<u8> {
+    let mut image = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    image.extend(pixels);
+    image
+}
+
+pub fn scenic_heatmap(analysis: &ForestAnalysis) -> Vec<u8> {
+    let scores = &analysis.scenic_scores;
+    let max_score = scores.iter().flatten().max().copied().unwrap_or(0).max(1);
+    pgm(
+        scores.first().map_or(0, |row| row.len()),
+        scores.len(),
+        scores
+            .iter()
+            .flatten()
+            .map(|score| (score * 255 / max_score) as u8),
+    )
+}
+
+pub fn visibility_mask(analysis: &ForestAnalysis) -> Vec<u8> {
+    let visible = &analysis.visible;
+    pgm(
+        visible.first().map_or(0, |row| row.len()),
+        visible.len(),
+        visible
+            .iter()
+            .flatten()
+            .map(|visible| if *visible { 255 } else { 0 }),
+    )
+}
+
+pub fn annotated_view(forest: &[Vec<Option<Tree>>], analysis: &ForestAnalysis) -> String {
+    let best = analysis.best_location();
+    let mut view = forest
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, tree)| {
+                    if best.is_some_and(|(best_x, best_y, _)| (best_x, best_y) == (x, y)) {
+                        'X'
+                    } else if !analysis.visible[y][x] {
+                        tree.map_or(' ', |_| '.')
+                    } else {
+                        tree.and_then(|tree| char::from_digit(tree.size as u32, 10))
+                            .unwrap_or('#')
+                    }
+                })
+                .collect::<String>()
+                + "\n"
+        })
+        .collect::<String>();
+    if let Some((x, y, score)) = best {
+        view.push_str(&format!(
+            "X: best treehouse at ({}, {}), score {}\n",
+            x, y, score
+        ));
+    }
+    view
+}
@@ -0,0 +1,85 @@
+use super::{get_tree, Tree};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Observer {
+    pub x: i32,
+    pub y: i32,
+    pub eye_height: i32,
+}
+
+pub fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let mut error = dx + dy;
+    let mut current = from;
+    let mut cells = vec![current];
+    while current != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            current.0 += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            current.1 += step_y;
+        }
+        cells.push(current);
+    }
+    cells
+}
+
+// The observer's eye sits `eye_height` above the top of its own tree, and the sight line runs
+// to the top of the target tree. Any tree in between reaching the sight line blocks the view.
+// An observer always sees the tree it stands on.
+pub fn can_see(forest: &[Vec<Option<Tree>>], observer: Observer, target: (i32, i32)) -> bool {
+    let (Some(from), Some(to)) = (
+        get_tree(forest, observer.x, observer.y),
+        get_tree(forest, target.0, target.1),
+    ) else {
+        return false;
+    };
+    if target == (observer.x, observer.y) {
+        return true;
+    }
+    let eye = from.size as i64 + observer.eye_height as i64;
+    let cells = line((observer.x, observer.y), target);
+    let steps = cells.len() as i64 - 1;
+    cells[1..cells.len().saturating_sub(1)]
+        .iter()
+        .enumerate()
+        .all(|(i, (x, y))| match get_tree(forest, *x, *y) {
+            Some(tree) => {
+                (tree.size as i64) * steps < eye * steps + (to.size as i64 - eye) * (i as i64 + 1)
+            }
+            None => true,
+        })
+}
+
+pub fn visible_from(forest: &[Vec<Option<Tree>>], observer: Observer) -> Vec<Tree> {
+    forest
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|tree| (tree.x, tree.y) != (observer.x, observer.y))
+        .filter(|tree| can_see(forest, observer, (tree.x, tree.y)))
+        .copied()
+        .collect()
+}
+
+pub fn seen_by(forest: &[Vec<Option<Tree>>], target: (i32, i32), eye_height: i32) -> Vec<Tree> {
+    forest
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|tree| (tree.x, tree.y) != target)
+        .filter(|tree| {
+            let observer = Observer {
+                x: tree.x,
+                y: tree.y,
+                eye_height,
+            };
+            can_see(forest, observer, target)
+        })
+        .copied()
+        .collect()
+}
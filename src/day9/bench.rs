@@ -0,0 +1,104 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use super::{parse_moves, visit_stream, Move, Position, RopeConfig};
+
+// The original implementation, kept as the baseline: a new vector of knots on every step and
+// a `HashSet` with the default hasher for the tail.
+fn legacy_visit(knots: Vec<Position>, moves: &[Move]) -> usize {
+    if knots.is_empty() {
+        return 0;
+    }
+
+    let mut knots = knots;
+    let mut visited_positions = HashSet::<Position>::new();
+    visited_positions.insert(*knots.last().unwrap());
+
+    moves.iter().for_each(|m| {
+        (0..m.amount).for_each(|_| {
+            let mut moved_knots = vec![];
+            moved_knots.push(knots.first().unwrap().do_move(&m.direction));
+
+            for knot in knots.iter().skip(1) {
+                moved_knots.push(knot.follow(moved_knots.last().unwrap(), 1));
+            }
+
+            visited_positions.insert(*moved_knots.last().unwrap());
+            knots = moved_knots;
+        });
+    });
+
+    visited_positions.len()
+}
+
+fn generate_moves(count: usize) -> String {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..count)
+        .map(|_| {
+            let direction = ["R", "D", "L", "U"][(next() % 4) as usize];
+            format!("{} {}\n", direction, next() % 20 + 1)
+        })
+        .collect()
+}
+
+pub struct BenchmarkResult {
+    pub moves: usize,
+    pub legacy_count: usize,
+    pub legacy_time: Duration,
+    pub count: usize,
+    pub time: Duration,
+}
+
+impl fmt::Display for BenchmarkResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "legacy:    {} positions in {:?} (parsed up front)",
+            self.legacy_count, self.legacy_time
+        )?;
+        writeln!(f, "streaming: {} positions in {:?}", self.count, self.time)?;
+        write!(
+            f,
+            "{} moves, {:.1}x faster",
+            self.moves,
+            self.legacy_time.as_secs_f64() / self.time.as_secs_f64().max(f64::EPSILON)
+        )
+    }
+}
+
+pub fn benchmark(moves: usize, length: usize) -> BenchmarkResult {
+    let input = generate_moves(moves);
+
+    let start = Instant::now();
+    let (_, parsed) = parse_moves(input.trim_end()).unwrap();
+    let legacy_count = legacy_visit(vec![Position { x: 0, y: 0 }; length], &parsed);
+    let legacy_time = start.elapsed();
+    drop(parsed);
+
+    let config = RopeConfig {
+        length,
+        tracked: Some(vec![length.saturating_sub(1)]),
+        ..RopeConfig::default()
+    };
+    let start = Instant::now();
+    let visited = visit_stream(&config, input.as_bytes()).unwrap();
+    let count = visited.values().next().map_or(0, |visited| visited.len());
+    let time = start.elapsed();
+
+    BenchmarkResult {
+        moves,
+        legacy_count,
+        legacy_time,
+        count,
+        time,
+    }
+}
@@ -1,5 +1,16 @@
-use std::{collections::HashSet, fs};
+mod bench;
+mod render;
+mod visited;
 
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use bench::benchmark;
 use nom::{
     bytes::complete::tag,
     character::complete::{alpha1, digit1, line_ending},
@@ -7,12 +18,18 @@ use nom::{
     sequence::separated_pair,
     IResult,
 };
+use render::{render_visited, write_frames, FrameOptions};
+use visited::VisitedCells;
 
 enum Direction {
     Right,
     Down,
     Left,
     Up,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
 struct Move {
@@ -20,20 +37,20 @@ struct Move {
     amount: u32,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 struct Position {
     x: i32,
     y: i32,
 }
 
 impl Position {
-    fn touching(&self, other: &Position) -> bool {
-        (self.x - other.x).abs() < 2 && (self.y - other.y).abs() < 2
+    fn touching(&self, other: &Position, slack: i32) -> bool {
+        (self.x - other.x).abs() <= slack && (self.y - other.y).abs() <= slack
     }
 
-    fn follow(&self, other: &Position) -> Position {
-        if self.touching(other) {
-            return self.clone();
+    fn follow(&self, other: &Position, slack: i32) -> Position {
+        if self.touching(other, slack) {
+            return *self;
         }
         let dx = (other.x - self.x).signum();
         let dy = (other.y - self.y).signum();
@@ -44,6 +61,10 @@ impl Position {
     }
 
     fn do_move(&self, direction: &Direction) -> Position {
+        let diagonal = |dx, dy| Position {
+            x: self.x + dx,
+            y: self.y + dy,
+        };
         match direction {
             Direction::Right => Position {
                 x: self.x + 1,
@@ -61,6 +82,10 @@ impl Position {
                 x: self.x,
                 y: self.y + 1,
             },
+            Direction::UpLeft => diagonal(-1, 1),
+            Direction::UpRight => diagonal(1, 1),
+            Direction::DownLeft => diagonal(-1, -1),
+            Direction::DownRight => diagonal(1, -1),
         }
     }
 }
@@ -72,7 +97,16 @@ fn parse_move(input: &str) -> IResult<&str, Move> {
         "D" => Direction::Down,
         "L" => Direction::Left,
         "U" => Direction::Up,
-        _ => panic!("Invalid direction: {direction}"),
+        "UL" => Direction::UpLeft,
+        "UR" => Direction::UpRight,
+        "DL" => Direction::DownLeft,
+        "DR" => Direction::DownRight,
+        _ => {
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                direction,
+                nom::error::ErrorKind::Tag,
+            )))
+        }
     };
     let amount = amount.parse::<u32>().unwrap();
     let m = Move { direction, amount };
@@ -83,41 +117,419 @@ fn parse_moves(input: &str) -> IResult<&str, Vec<Move>> {
     separated_list1(line_ending, parse_move)(input)
 }
 
-fn visit(knots: Vec<Position>, moves: &Vec<Move>) -> usize {
-    if knots.is_empty() {
-        return 0;
+struct RopeConfig {
+    length: usize,
+    slack: Vec<i32>,
+    tracked: Option<Vec<usize>>,
+}
+
+impl Default for RopeConfig {
+    fn default() -> Self {
+        RopeConfig {
+            length: 2,
+            slack: vec![1],
+            tracked: None,
+        }
+    }
+}
+
+impl RopeConfig {
+    fn slack(&self, knot: usize) -> i32 {
+        self.slack
+            .get(knot - 1)
+            .or(self.slack.last())
+            .copied()
+            .unwrap_or(1)
+    }
+
+    fn tracked(&self) -> Vec<usize> {
+        match &self.tracked {
+            Some(tracked) => tracked
+                .iter()
+                .copied()
+                .filter(|knot| *knot < self.length)
+                .collect(),
+            None => (0..self.length).collect(),
+        }
     }
+}
 
-    let mut knots = knots;
-    let mut visited_positions = HashSet::<Position>::new();
-    visited_positions.insert(knots.last().unwrap().clone());
+struct MoveReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+}
+
+impl<R: BufRead> MoveReader<R> {
+    fn new(reader: R) -> MoveReader<R> {
+        MoveReader {
+            reader,
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+}
 
-    moves.iter().for_each(|m| {
-        (0..m.amount).for_each(|_| {
-            let mut moved_knots = vec![];
-            moved_knots.push(knots.first().unwrap().do_move(&m.direction));
+impl<R: BufRead> Iterator for MoveReader<R> {
+    type Item = io::Result<Move>;
 
-            for i in 1..knots.len() {
-                moved_knots.push(knots[i].follow(moved_knots.last().unwrap()));
+    fn next(&mut self) -> Option<io::Result<Move>> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(error) => return Some(Err(error)),
+            }
+            let line = self.line.trim();
+            if line.is_empty() {
+                continue;
             }
+            return Some(match parse_move(line) {
+                Ok(("", m)) => Ok(m),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: invalid move `{}`", self.line_number, line),
+                )),
+            });
+        }
+    }
+}
 
-            visited_positions.insert(moved_knots.last().unwrap().clone());
-            knots = moved_knots;
-        });
-    });
+struct Rope {
+    knots: Vec<Position>,
+    slack: Vec<i32>,
+}
+
+impl Rope {
+    fn new(config: &RopeConfig) -> Rope {
+        Rope {
+            knots: vec![Position { x: 0, y: 0 }; config.length],
+            slack: (0..config.length)
+                .map(|knot| if knot == 0 { 0 } else { config.slack(knot) })
+                .collect(),
+        }
+    }
+
+    fn step(&mut self, direction: &Direction) {
+        let Some(head) = self.knots.first_mut() else {
+            return;
+        };
+        *head = head.do_move(direction);
+        for i in 1..self.knots.len() {
+            let moved = self.knots[i].follow(&self.knots[i - 1], self.slack[i]);
+            // A knot that stays put leaves every knot behind it in place as well.
+            if moved == self.knots[i] {
+                break;
+            }
+            self.knots[i] = moved;
+        }
+    }
+}
 
-    visited_positions.len()
+struct Trajectory<'a> {
+    rope: Rope,
+    moves: std::slice::Iter<'a, Move>,
+    direction: Option<&'a Direction>,
+    remaining: u32,
+}
+
+impl Iterator for Trajectory<'_> {
+    type Item = Vec<Position>;
+
+    fn next(&mut self) -> Option<Vec<Position>> {
+        while self.remaining == 0 {
+            let m = self.moves.next()?;
+            self.direction = Some(&m.direction);
+            self.remaining = m.amount;
+        }
+        self.remaining -= 1;
+        self.rope.step(self.direction?);
+        Some(self.rope.knots.clone())
+    }
+}
+
+fn trajectory<'a>(config: &RopeConfig, moves: &'a [Move]) -> Trajectory<'a> {
+    Trajectory {
+        rope: Rope::new(config),
+        moves: moves.iter(),
+        direction: None,
+        remaining: 0,
+    }
+}
+
+struct Visitor {
+    rope: Rope,
+    visited: BTreeMap<usize, VisitedCells>,
+}
+
+impl Visitor {
+    fn new(config: &RopeConfig) -> Visitor {
+        Visitor {
+            rope: Rope::new(config),
+            visited: config
+                .tracked()
+                .into_iter()
+                .map(|knot| {
+                    let mut visited = VisitedCells::default();
+                    visited.insert(Position { x: 0, y: 0 });
+                    (knot, visited)
+                })
+                .collect(),
+        }
+    }
+
+    fn apply(&mut self, m: &Move) {
+        for _ in 0..m.amount {
+            self.rope.step(&m.direction);
+            for (knot, visited) in self.visited.iter_mut() {
+                visited.insert(self.rope.knots[*knot]);
+            }
+        }
+    }
+}
+
+fn visit(config: &RopeConfig, moves: &[Move]) -> BTreeMap<usize, VisitedCells> {
+    let mut visitor = Visitor::new(config);
+    moves.iter().for_each(|m| visitor.apply(m));
+    visitor.visited
+}
+
+fn visit_stream<R: BufRead>(
+    config: &RopeConfig,
+    reader: R,
+) -> io::Result<BTreeMap<usize, VisitedCells>> {
+    let mut visitor = Visitor::new(config);
+    for m in MoveReader::new(reader) {
+        visitor.apply(&m?);
+    }
+    Ok(visitor.visited)
+}
+
+fn tail_visited_count(length: usize, moves: &[Move]) -> usize {
+    let config = RopeConfig {
+        length,
+        tracked: Some(vec![length.saturating_sub(1)]),
+        ..RopeConfig::default()
+    };
+    visit(&config, moves)
+        .values()
+        .next()
+        .map_or(0, |visited| visited.len())
+}
+
+fn parse_list<T: std::str::FromStr>(value: &str) -> Vec<T> {
+    value
+        .split(',')
+        .filter_map(|value| value.trim().parse::<T>().ok())
+        .collect()
 }
 
 fn main() {
     let input = fs::read_to_string("src/day9/input.txt").unwrap();
     let (_, moves) = parse_moves(&input).unwrap();
 
-    let short_rope = vec![Position { x: 0, y: 0 }; 2];
-    let short_rope_visited_count = visit(short_rope, &moves);
-    println!("Visited positions: {}", short_rope_visited_count);
+    println!("Visited positions: {}", tail_visited_count(2, &moves));
+    println!("Visited positions: {}", tail_visited_count(10, &moves));
+
+    let mut config = RopeConfig::default();
+    let mut configured = false;
+    let mut frame_every = None;
+    let mut frame_scale = 4;
+    let mut frames_directory = None;
+    let mut render = false;
+    let mut bench_moves = None;
+    for arg in env::args().skip(1) {
+        if let Some(count) = arg.strip_prefix("--bench=") {
+            bench_moves = Some(count.parse::<usize>().unwrap());
+            continue;
+        } else if arg == "--render" {
+            render = true;
+            continue;
+        } else if let Some(directory) = arg.strip_prefix("--frames=") {
+            frames_directory = Some(directory.to_string());
+            continue;
+        } else if let Some(every) = arg.strip_prefix("--every=") {
+            frame_every = Some(every.parse::<usize>().unwrap().max(1));
+            continue;
+        } else if let Some(scale) = arg.strip_prefix("--scale=") {
+            frame_scale = scale.parse::<usize>().unwrap().max(1);
+            continue;
+        }
+        if let Some(length) = arg.strip_prefix("--knots=") {
+            config.length = length.parse::<usize>().unwrap();
+        } else if let Some(slack) = arg.strip_prefix("--slack=") {
+            config.slack = parse_list(slack);
+        } else if let Some(tracked) = arg.strip_prefix("--track=") {
+            config.tracked = Some(parse_list(tracked));
+        } else {
+            continue;
+        }
+        configured = true;
+    }
+    if let Some(moves) = bench_moves {
+        println!("{}", benchmark(moves, config.length));
+    }
+    if configured {
+        let input = BufReader::new(File::open("src/day9/input.txt").unwrap());
+        for (knot, visited) in visit_stream(&config, input).unwrap() {
+            println!("Knot {} visited positions: {}", knot, visited.len());
+        }
+    }
+
+    if render {
+        let tail = config.length.saturating_sub(1);
+        config.tracked = Some(vec![tail]);
+        if let Some(visited) = visit(&config, &moves).get(&tail) {
+            let knots = trajectory(&config, &moves).last();
+            print!("{}", render_visited(visited, knots.as_deref()));
+        }
+    }
+    if let Some(directory) = frames_directory {
+        // Every step of a full input is thousands of frames, so the interval must be explicit.
+        let Some(every) = frame_every else {
+            eprintln!("--frames needs --every=N to choose how many steps each frame covers");
+            return;
+        };
+        let options = FrameOptions {
+            every,
+            scale: frame_scale,
+        };
+        let count = write_frames(&config, &moves, Path::new(&directory), &options).unwrap();
+        println!("Wrote {} frames to {}", count, directory);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_part_1() {
+        let input = fs::read_to_string("src/day9/test.txt").unwrap();
+        let (_, moves) = parse_moves(&input).unwrap();
+        assert_eq!(tail_visited_count(2, &moves), 13);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let input = fs::read_to_string("src/day9/test.txt").unwrap();
+        let (_, moves) = parse_moves(&input).unwrap();
+        assert_eq!(tail_visited_count(10, &moves), 1);
+
+        let (_, moves) = parse_moves("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20").unwrap();
+        assert_eq!(tail_visited_count(10, &moves), 36);
+    }
+
+    #[test]
+    fn test_rope_config() {
+        let (_, moves) = parse_moves("UR 3").unwrap();
+        let visited = visit(&RopeConfig::default(), &moves);
+        assert_eq!(visited[&0].len(), 4);
+        assert_eq!(
+            visited[&1].positions().collect::<HashSet<Position>>(),
+            HashSet::from([
+                Position { x: 0, y: 0 },
+                Position { x: 1, y: 1 },
+                Position { x: 2, y: 2 }
+            ])
+        );
+
+        let (_, moves) = parse_moves("R 5").unwrap();
+        let config = RopeConfig {
+            length: 3,
+            slack: vec![2, 1],
+            tracked: Some(vec![1, 2, 7]),
+        };
+        let visited = visit(&config, &moves);
+        assert_eq!(visited.keys().copied().collect::<Vec<usize>>(), vec![1, 2]);
+        assert_eq!(visited[&1].len(), 4);
+        assert_eq!(visited[&2].len(), 3);
 
-    let long_rope = vec![Position { x: 0, y: 0 }; 10];
-    let long_rope_visited_count = visit(long_rope, &moves);
-    println!("Visited positions: {}", long_rope_visited_count);
+        let (_, moves) = parse_moves("R 200000\nU 200000\nL 3").unwrap();
+        let visited = visit(&RopeConfig::default(), &moves);
+        assert_eq!(visited[&1].len(), 400_001);
+        assert!(visited[&1].contains(Position {
+            x: 200_000,
+            y: 199_999
+        }));
+        assert!(!visited[&1].contains(Position {
+            x: 100_000,
+            y: 100_000
+        }));
+        assert!(visited[&1].contains(Position {
+            x: 199_998,
+            y: 200_000
+        }));
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let input = fs::read_to_string("src/day9/test.txt").unwrap();
+        let (_, moves) = parse_moves(&input).unwrap();
+        let config = RopeConfig::default();
+        let steps = trajectory(&config, &moves).collect::<Vec<Vec<Position>>>();
+        assert_eq!(steps.len(), 24);
+        assert_eq!(
+            steps[4],
+            vec![Position { x: 4, y: 1 }, Position { x: 3, y: 0 }]
+        );
+
+        let visited = visit(&config, &moves);
+        assert_eq!(
+            render_visited(&visited[&1], None),
+            "..##.\n...##\n.####\n....#\ns###.\n"
+        );
+        assert_eq!(
+            render_visited(&visited[&1], steps.last().map(|knots| knots.as_slice())),
+            "..##.\n...##\n.1H##\n....#\ns###.\n"
+        );
+
+        let directory = env::temp_dir().join(format!("day9_frames_{}", std::process::id()));
+        let options = FrameOptions { every: 5, scale: 2 };
+        assert_eq!(
+            write_frames(&config, &moves, &directory, &options).unwrap(),
+            4
+        );
+        let frame = fs::read(directory.join("frame_00003.pgm")).unwrap();
+        let header = b"P5\n12 10\n255\n";
+        assert_eq!(&frame[..header.len()], header);
+        assert_eq!(frame.len(), header.len() + 120);
+        for (frame, step) in [("frame_00000.pgm", 4), ("frame_00003.pgm", 19)] {
+            let knot_cells = steps[step].iter().collect::<HashSet<&Position>>().len();
+            let frame = fs::read(directory.join(frame)).unwrap();
+            let white = frame[header.len()..]
+                .iter()
+                .filter(|pixel| **pixel == 255)
+                .count();
+            assert_eq!(white, knot_cells * 4);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input = fs::read_to_string("src/day9/test.txt").unwrap();
+        let config = RopeConfig::default();
+        let visited = visit_stream(&config, input.as_bytes()).unwrap();
+        assert_eq!(visited[&1].len(), 13);
+        assert!(visited[&1].contains(Position { x: 4, y: 2 }));
+        assert!(!visited[&1].contains(Position { x: 0, y: 4 }));
+
+        let error = visit_stream(&config, "R 4\n\nX 2\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: invalid move `X 2`");
+        assert!(visit_stream(&config, "R 4 U".as_bytes()).is_err());
+
+        let mut cells = VisitedCells::default();
+        assert!(cells.insert(Position { x: 1000, y: -2000 }));
+        assert!(cells.insert(Position { x: -5, y: 7 }));
+        assert!(!cells.insert(Position { x: 1000, y: -2000 }));
+        assert!(cells.contains(Position { x: -5, y: 7 }));
+        assert_eq!(cells.len(), 2);
+
+        let result = benchmark(2_000, 10);
+        assert_eq!(result.legacy_count, result.count);
+    }
 }
@@ -0,0 +1,152 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    iter,
+    path::Path,
+};
+
+use super::{trajectory, visited::VisitedCells, Move, Position, RopeConfig};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min_x: i32,
+    pub max_x: i32,
+    pub min_y: i32,
+    pub max_y: i32,
+}
+
+impl Bounds {
+    pub fn new(position: &Position) -> Bounds {
+        Bounds {
+            min_x: position.x,
+            max_x: position.x,
+            min_y: position.y,
+            max_y: position.y,
+        }
+    }
+
+    pub fn include(&mut self, position: &Position) {
+        self.min_x = self.min_x.min(position.x);
+        self.max_x = self.max_x.max(position.x);
+        self.min_y = self.min_y.min(position.y);
+        self.max_y = self.max_y.max(position.y);
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+
+    // Rows run from the top of the map down, so larger y values come first.
+    fn rows(&self) -> impl Iterator<Item = i32> {
+        (self.min_y..=self.max_y).rev()
+    }
+
+    fn columns(&self) -> impl Iterator<Item = i32> {
+        self.min_x..=self.max_x
+    }
+}
+
+fn knot_label(knot: usize) -> char {
+    match knot {
+        0 => 'H',
+        knot => char::from_digit(knot as u32 % 36, 36).unwrap(),
+    }
+}
+
+pub fn render_visited(visited: &VisitedCells, knots: Option<&[Position]>) -> String {
+    let start = Position { x: 0, y: 0 };
+    let mut bounds = Bounds::new(&start);
+    visited
+        .positions()
+        .chain(knots.unwrap_or_default().iter().copied())
+        .for_each(|position| bounds.include(&position));
+
+    let mut map = String::new();
+    for y in bounds.rows() {
+        for x in bounds.columns() {
+            let position = Position { x, y };
+            let knot = knots.and_then(|knots| knots.iter().position(|knot| *knot == position));
+            map.push(match knot {
+                Some(knot) => knot_label(knot),
+                None if position == start => 's',
+                None if visited.contains(position) => '#',
+                None => '.',
+            });
+        }
+        map.push('\n');
+    }
+    map
+}
+
+pub struct FrameOptions {
+    pub every: usize,
+    pub scale: usize,
+}
+
+fn write_pgm(path: &Path, pixels: &[u8], width: usize, scale: usize) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(
+        file,
+        "P5\n{} {}\n255\n",
+        width * scale,
+        pixels.len() / width * scale
+    )?;
+    let mut scaled_row = Vec::with_capacity(width * scale);
+    for row in pixels.chunks(width) {
+        scaled_row.clear();
+        row.iter()
+            .for_each(|pixel| scaled_row.extend(iter::repeat_n(*pixel, scale)));
+        for _ in 0..scale {
+            file.write_all(&scaled_row)?;
+        }
+    }
+    file.flush()
+}
+
+// Writes one PGM image per `every` steps: the tail's visited cells in gray, knots in white.
+// All frames share the bounds of the whole trajectory so they can be played back in sequence.
+pub fn write_frames(
+    config: &RopeConfig,
+    moves: &[Move],
+    directory: &Path,
+    options: &FrameOptions,
+) -> io::Result<usize> {
+    let mut bounds = Bounds::new(&Position { x: 0, y: 0 });
+    trajectory(config, moves)
+        .flatten()
+        .for_each(|position| bounds.include(&position));
+    let (width, height) = (bounds.width(), bounds.height());
+
+    fs::create_dir_all(directory)?;
+    let mut pixels = vec![0u8; width * height];
+    let index = |position: &Position| {
+        (bounds.max_y - position.y) as usize * width + (position.x - bounds.min_x) as usize
+    };
+    let mut count = 0;
+    for (step, knots) in trajectory(config, moves).enumerate() {
+        if let Some(tail) = knots.last() {
+            pixels[index(tail)] = 128;
+        }
+        if (step + 1) % options.every != 0 {
+            continue;
+        }
+
+        let covered = knots
+            .iter()
+            .map(|knot| (index(knot), pixels[index(knot)]))
+            .collect::<Vec<(usize, u8)>>();
+        covered.iter().for_each(|(i, _)| pixels[*i] = 255);
+        let path = directory.join(format!("frame_{:05}.pgm", count));
+        write_pgm(&path, &pixels, width, options.scale)?;
+        covered
+            .iter()
+            .rev()
+            .for_each(|(i, pixel)| pixels[*i] = *pixel);
+        count += 1;
+    }
+    Ok(count)
+}
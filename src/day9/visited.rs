@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use super::Position;
+
+const TILE_SIZE: i32 = 64;
+
+// A bitmap split into 64x64 tiles, one u64 per tile row, so memory follows the cells
+// actually visited rather than the area of their bounding box.
+#[derive(Debug, Default)]
+pub struct VisitedCells {
+    tiles: HashMap<(i32, i32), [u64; TILE_SIZE as usize]>,
+    count: usize,
+}
+
+impl VisitedCells {
+    fn locate(position: Position) -> ((i32, i32), usize, u64) {
+        let tile = (
+            position.x.div_euclid(TILE_SIZE),
+            position.y.div_euclid(TILE_SIZE),
+        );
+        let row = position.y.rem_euclid(TILE_SIZE) as usize;
+        let bit = 1 << position.x.rem_euclid(TILE_SIZE);
+        (tile, row, bit)
+    }
+
+    pub fn insert(&mut self, position: Position) -> bool {
+        let (tile, row, bit) = VisitedCells::locate(position);
+        let rows = self.tiles.entry(tile).or_insert([0; TILE_SIZE as usize]);
+        if rows[row] & bit != 0 {
+            return false;
+        }
+        rows[row] |= bit;
+        self.count += 1;
+        true
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        let (tile, row, bit) = VisitedCells::locate(position);
+        self.tiles
+            .get(&tile)
+            .is_some_and(|rows| rows[row] & bit != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.tiles.iter().flat_map(|((tile_x, tile_y), rows)| {
+            rows.iter().enumerate().flat_map(move |(row, bits)| {
+                (0..TILE_SIZE)
+                    .filter(move |bit| bits & (1 << bit) != 0)
+                    .map(move |bit| Position {
+                        x: tile_x * TILE_SIZE + bit,
+                        y: tile_y * TILE_SIZE + row as i32,
+                    })
+            })
+        })
+    }
+}